@@ -0,0 +1,144 @@
+// this module works out which squares each player controls, worked out once for the position
+// so evaluation terms and heatmaps do not walk the move map for every square
+
+use crate::bitboard::{self, Squares};
+use crate::chess::{ChessInstant, Piece, Player};
+use crate::square::Square;
+
+/// a set of squares on the board
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SquareSet(u64);
+
+impl SquareSet {
+    /// the set with nothing in it
+    pub fn new() -> SquareSet {
+        SquareSet(0)
+    }
+
+    /// determines if the square is in the set
+    pub fn contains(&self, square: Square) -> bool {
+        self.0 & 1 << square.index() != 0
+    }
+
+    /// adds the square to the set
+    pub fn insert(&mut self, square: Square) {
+        self.0 |= 1 << square.index();
+    }
+
+    /// the number of squares in the set
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// determines if there are no squares in the set
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// every square in the set going across each row from a8
+    pub fn iter(&self) -> impl Iterator<Item = Square> {
+        Squares(self.0).map(Square::at_index)
+    }
+
+    /// the set as a bitboard where bit row * 8 + col is the square
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for SquareSet {
+    fn from(bits: u64) -> SquareSet {
+        SquareSet(bits)
+    }
+}
+
+/// how many pieces of each player attack every square
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AttackMap {
+    counts: [[u8; 64]; 2],
+    attacked: [u64; 2],
+}
+
+impl AttackMap {
+    /// the number of the player's pieces attacking the square, pieces behind another
+    /// on the same line are not counted
+    pub fn attackers(&self, player: &Player, square: Square) -> u8 {
+        self.counts[bitboard::side(player)][square.index()]
+    }
+
+    /// every square the player attacks
+    pub fn attacks_by(&self, player: &Player) -> SquareSet {
+        SquareSet(self.attacked[bitboard::side(player)])
+    }
+
+    /// the attacker counts laid out like the board for drawing a heatmap
+    pub fn grid(&self, player: &Player) -> [[u8; 8]; 8] {
+        let counts = &self.counts[bitboard::side(player)];
+        let mut grid = [[0; 8]; 8];
+        for (square, count) in counts.iter().enumerate() {
+            grid[square / 8][square % 8] = *count;
+        }
+        grid
+    }
+
+    /// player 1's attackers minus player 2's on the square, above 0 when player 1 controls it
+    pub fn control(&self, square: Square) -> i32 {
+        self.attackers(&Player::P1, square) as i32 - self.attackers(&Player::P2, square) as i32
+    }
+}
+
+impl ChessInstant {
+    /// every square the player's pieces attack, squares holding the player's own pieces
+    /// are included when they are defended
+    pub fn attacks_by(&self, player: &Player) -> SquareSet {
+        let mut attacked = 0;
+        self.each_attack(player, |attacks| attacked |= attacks);
+        SquareSet(attacked)
+    }
+
+    /// the attacker counts of both players for every square
+    pub fn attack_map(&self) -> AttackMap {
+        let mut map = AttackMap {
+            counts: [[0; 64]; 2],
+            attacked: [0; 2],
+        };
+        for player in [Player::P1, Player::P2] {
+            let side = bitboard::side(&player);
+            self.each_attack(&player, |attacks| {
+                map.attacked[side] |= attacks;
+                for square in Squares(attacks) {
+                    map.counts[side][square] += 1;
+                }
+            });
+        }
+        map
+    }
+
+    /// calls f with the squares attacked by each of the player's pieces
+    fn each_attack(&self, player: &Player, mut f: impl FnMut(u64)) {
+        let occupied = self.bits.occupied();
+        let side = bitboard::side(player);
+        for piece in [
+            Piece::Rook,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Queen,
+            Piece::King,
+            Piece::Pawn,
+        ] {
+            for from in Squares(self.bits.pieces(piece.to_id(player))) {
+                f(match piece {
+                    Piece::Rook => bitboard::rook_attacks(from, occupied),
+                    Piece::Knight => bitboard::KNIGHT_ATTACKS[from],
+                    Piece::Bishop => bitboard::bishop_attacks(from, occupied),
+                    Piece::Queen => {
+                        bitboard::rook_attacks(from, occupied)
+                            | bitboard::bishop_attacks(from, occupied)
+                    }
+                    Piece::King => bitboard::KING_ATTACKS[from],
+                    Piece::Pawn => bitboard::PAWN_ATTACKS[side][from],
+                });
+            }
+        }
+    }
+}
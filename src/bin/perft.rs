@@ -0,0 +1,61 @@
+// runs perft from the command line to find move generation bugs move by move
+//
+// usage: perft <depth> [fen] [--divide]
+// the fen defaults to the starting position, --divide prints the count under each move
+
+use std::process;
+use std::time::Instant;
+
+use chess::chess::ChessInstant;
+use chess::perft::{perft, perft_divide, PerftCounts};
+
+fn main() {
+    let mut divide = false;
+    let mut args = Vec::new();
+    for arg in std::env::args().skip(1) {
+        if arg == "--divide" {
+            divide = true;
+        } else {
+            args.push(arg);
+        }
+    }
+    let depth = match args.first().map(|depth| depth.parse::<u32>()) {
+        Some(Ok(depth)) => depth,
+        _ => {
+            eprintln!("usage: perft <depth> [fen] [--divide]");
+            process::exit(2);
+        }
+    };
+    let chess_instant = match args.get(1) {
+        Some(fen) => match ChessInstant::from_fen(fen) {
+            Ok(chess_instant) => chess_instant,
+            Err(err) => {
+                eprintln!("{err}");
+                process::exit(2);
+            }
+        },
+        None => ChessInstant::new(),
+    };
+
+    let time = Instant::now();
+    // there is nothing to divide at depth 0, the position itself is the one node
+    let counts = if divide && depth > 0 {
+        let mut total = PerftCounts::default();
+        for (mv, counts) in perft_divide(&chess_instant, depth) {
+            println!("{}: {}", chess_instant.move_to_uci(mv), counts.nodes);
+            total += counts;
+        }
+        println!();
+        total
+    } else {
+        perft(&chess_instant, depth)
+    };
+    println!("nodes       {}", counts.nodes);
+    println!("captures    {}", counts.captures);
+    println!("en passants {}", counts.en_passants);
+    println!("castles     {}", counts.castles);
+    println!("promotions  {}", counts.promotions);
+    println!("checks      {}", counts.checks);
+    println!("checkmates  {}", counts.checkmates);
+    println!("time        {} milliseconds", time.elapsed().as_millis());
+}
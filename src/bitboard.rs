@@ -0,0 +1,232 @@
+// this module holds the bitboard sets of pieces and the attack tables used to generate moves,
+// bit index is row * 8 + col the same as Square::index, so bit 0 is row 0 col 0
+
+use crate::chess::Player;
+
+/// a set of squares for every piece id and for each player,
+/// id 6 holds the empty squares so it can be updated the same way as the pieces
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Bitboards {
+    pieces: [u64; 13],
+    players: [u64; 2],
+}
+
+impl Bitboards {
+    /// moves the square from holding the old id to holding the new id
+    pub(crate) fn set(&mut self, square: usize, old: u32, new: u32) {
+        let bit = 1 << square;
+        if old < 13 {
+            self.pieces[old as usize] &= !bit;
+        }
+        if new < 13 {
+            self.pieces[new as usize] |= bit;
+        }
+        // clear then set so a square moving from empty to empty is left alone
+        self.players[0] &= !bit;
+        self.players[1] &= !bit;
+        if new < 6 {
+            self.players[0] |= bit;
+        } else if new > 6 && new < 13 {
+            self.players[1] |= bit;
+        }
+    }
+
+    /// the set of squares holding the piece id
+    pub(crate) fn pieces(&self, id: u32) -> u64 {
+        self.pieces[id as usize]
+    }
+
+    /// the set of squares holding any piece of the player
+    pub(crate) fn player(&self, player: &Player) -> u64 {
+        self.players[side(player)]
+    }
+
+    /// the set of squares holding any piece
+    pub(crate) fn occupied(&self) -> u64 {
+        self.players[0] | self.players[1]
+    }
+}
+
+/// index into the per player tables
+pub(crate) fn side(player: &Player) -> usize {
+    match player {
+        Player::P1 => 0,
+        Player::P2 => 1,
+    }
+}
+
+/// iterates over the square indexes in a set from lowest to highest
+pub(crate) struct Squares(pub(crate) u64);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1; // clear the lowest bit
+        Some(square)
+    }
+}
+
+/// gives back the (row, column) for a square index
+pub(crate) fn row_col(square: usize) -> (usize, u32) {
+    (square >> 3, (square & 7) as u32)
+}
+
+// row and column steps for the pieces that jump to a fixed set of squares
+const KNIGHT_STEPS: [(i32, i32); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+const KING_STEPS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+// player 1 pawns move up the board toward row 0, player 2 pawns move down
+const P1_PAWN_STEPS: [(i32, i32); 2] = [(-1, -1), (-1, 1)];
+const P2_PAWN_STEPS: [(i32, i32); 2] = [(1, -1), (1, 1)];
+
+pub(crate) const KNIGHT_ATTACKS: [u64; 64] = step_table(&KNIGHT_STEPS);
+pub(crate) const KING_ATTACKS: [u64; 64] = step_table(&KING_STEPS);
+/// the squares a pawn attacks indexed by side then square
+pub(crate) const PAWN_ATTACKS: [[u64; 64]; 2] =
+    [step_table(&P1_PAWN_STEPS), step_table(&P2_PAWN_STEPS)];
+
+/// the light squares, row 0 col 0 is light
+pub(crate) const LIGHT_SQUARES: u64 = 0xaa55_aa55_aa55_aa55;
+
+// sliding directions, the first four move toward higher indexes
+const SOUTH: usize = 0;
+const EAST: usize = 1;
+const SOUTH_WEST: usize = 2;
+const SOUTH_EAST: usize = 3;
+const NORTH: usize = 4;
+const WEST: usize = 5;
+const NORTH_WEST: usize = 6;
+const NORTH_EAST: usize = 7;
+const DIRECTIONS: [(i32, i32); 8] = [
+    (1, 0),
+    (0, 1),
+    (1, -1),
+    (1, 1),
+    (-1, 0),
+    (0, -1),
+    (-1, -1),
+    (-1, 1),
+];
+
+/// the direction pointing the other way for each direction
+const OPPOSITE: [usize; 8] = [
+    NORTH, WEST, NORTH_EAST, NORTH_WEST, SOUTH, EAST, SOUTH_EAST, SOUTH_WEST,
+];
+
+/// every square reached from a square in a direction on an empty board, indexed by direction then square
+const RAYS: [[u64; 64]; 8] = ray_table();
+
+/// every square a rook on the square attacks given the occupied squares
+pub(crate) fn rook_attacks(square: usize, occupied: u64) -> u64 {
+    ray_attacks(square, occupied, NORTH)
+        | ray_attacks(square, occupied, SOUTH)
+        | ray_attacks(square, occupied, EAST)
+        | ray_attacks(square, occupied, WEST)
+}
+
+/// every square a bishop on the square attacks given the occupied squares
+pub(crate) fn bishop_attacks(square: usize, occupied: u64) -> u64 {
+    ray_attacks(square, occupied, NORTH_WEST)
+        | ray_attacks(square, occupied, NORTH_EAST)
+        | ray_attacks(square, occupied, SOUTH_WEST)
+        | ray_attacks(square, occupied, SOUTH_EAST)
+}
+
+/// the squares strictly between two squares on the same row, column or diagonal, 0 if they do not share one
+pub(crate) fn between(a: usize, b: usize) -> u64 {
+    for ray in &RAYS {
+        if ray[a] & 1 << b != 0 {
+            // the ray from b carries on past b in the same direction
+            return ray[a] & !ray[b] & !(1 << b);
+        }
+    }
+    0
+}
+
+/// every square on the whole row, column or diagonal through two squares, 0 if they do not share one
+pub(crate) fn line(a: usize, b: usize) -> u64 {
+    for (direction, opposite) in OPPOSITE.iter().enumerate() {
+        if RAYS[direction][a] & 1 << b != 0 {
+            return RAYS[direction][a] | RAYS[*opposite][a] | 1 << a;
+        }
+    }
+    0
+}
+
+/// the squares along the ray up to and including the first occupied square
+fn ray_attacks(square: usize, occupied: u64, direction: usize) -> u64 {
+    let ray = RAYS[direction][square];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    // the closest blocker is the lowest bit on rays moving to higher indexes and the highest otherwise
+    let blocker = if direction < NORTH {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray ^ RAYS[direction][blocker as usize]
+}
+
+/// builds the table of squares reached by a single step from every square
+const fn step_table(steps: &[(i32, i32)]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let (row, col) = ((square / 8) as i32, (square % 8) as i32);
+        let mut i = 0;
+        while i < steps.len() {
+            let (r, c) = (row + steps[i].0, col + steps[i].1);
+            if r >= 0 && r < 8 && c >= 0 && c < 8 {
+                table[square] |= 1 << (r * 8 + c);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+/// builds the rays in every direction from every square
+const fn ray_table() -> [[u64; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (dr, dc) = DIRECTIONS[direction];
+        let mut square = 0;
+        while square < 64 {
+            let (mut r, mut c) = ((square / 8) as i32 + dr, (square % 8) as i32 + dc);
+            while r >= 0 && r < 8 && c >= 0 && c < 8 {
+                table[direction][square] |= 1 << (r * 8 + c);
+                r += dr;
+                c += dc;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    table
+}
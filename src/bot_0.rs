@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use crate::{
     chess::{ChessInstant, Piece, Player},
     movemap::MoveMap,
+    square::Square,
+    status::GameStatus,
 };
 
 impl Piece {
@@ -50,15 +52,18 @@ pub fn bot_0_2_moves(ci: &mut ChessInstant, mm: &MoveMap) -> Vec<(ChessInstant,
 }
 
 fn eval_plie(ci: &mut ChessInstant, mm: &MoveMap, player: &Player, depth: u32) -> i32 {
-    let mut found = ci.valid_games(mm);
-
-    if found.len() == 0 {
-        if ci.player() == *player {
-            return i32::MIN; // being in check mate is as bad as it gets
-        } else {
-            return i32::MAX; // putting opponent in checkmate is as good as it gets
+    match ci.status(mm) {
+        GameStatus::Ongoing => {}
+        GameStatus::Checkmate { winner } => {
+            if winner == *player {
+                return i32::MAX; // putting opponent in checkmate is as good as it gets
+            } else {
+                return i32::MIN; // being in check mate is as bad as it gets
+            }
         }
+        _ => return 0, // neither player can win
     }
+    let mut found = ci.valid_games(mm);
 
     let mut graded: Vec<(ChessInstant, i32)> = found
         .iter_mut()
@@ -131,6 +136,8 @@ fn gradev2(ci: &mut ChessInstant, player: &Player, mm: &MoveMap) -> i32 {
 
     let opponent_moves_grade = ci.valid_games(mm).len() as i32 * (-1);
 
+    // the opponent is to move so these are the captures they could make
+    let captures = ci.capture_moves();
     let mut threat = 0;
     let mut trade_potential = 0;
     for row in 0..7 {
@@ -140,29 +147,20 @@ fn gradev2(ci: &mut ChessInstant, player: &Player, mm: &MoveMap) -> i32 {
                 continue;
             }
 
-            let (found_player, found_piece) = p.unwrap();
+            let (found_player, _) = p.unwrap();
             if found_player == *player {
-                // calculate trade potential
-                let threats: Vec<Piece> = ci.threatened_by(&row, &col, mm).iter().map(|id| Piece::from_id(id)).collect();
-                if threats.len() > 0 {
-                    let covered: Vec<Piece> = ci.protected_by(&row, &col, mm).iter().map(|id| Piece::from_id(id)).collect();
-                    if covered.len() > 0 {
-                        let mut min_threat = threats[0].value();
-                        threats.iter().for_each(|t| {
-                            if t.value() < min_threat {
-                                min_threat = t.value()
-                            }
-                        });
-                        trade_potential = min_threat - found_piece.value();
-                    }
-                    // if not covered by piece
-                    else {
-                        trade_potential = found_piece.value() * (-1);
-                    }
+                // calculate trade potential from the best exchange the opponent can start on the piece
+                let best_exchange = captures
+                    .iter()
+                    .filter(|mv| mv.to == Square::at(row, col))
+                    .map(|mv| ci.see(*mv))
+                    .max();
+                if let Some(exchange) = best_exchange {
+                    trade_potential = -exchange.max(0);
                 }
 
                 // calculate the max threat
-                let threatens: Vec<Piece> = ci.threatens(&row, &col, mm).iter().map(|id| Piece::from_id(id)).collect();
+                let threatens: Vec<Piece> = ci.targets(Square::at(row, col), mm).iter().map(|id| Piece::from_id(id)).collect();
                 if threatens.len() > 0 {
                     let mut max_threat = threatens[0].value();
                     threatens.iter().for_each(|t| {
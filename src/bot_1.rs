@@ -2,7 +2,11 @@ use std::ops::Range;
 
 use crate::{
     chess::{ChessInstant, Piece, Player},
+    game::{self, Game},
+    minmax::SearchState,
     movemap::MoveMap,
+    square::Square,
+    status::GameStatus,
 };
 use serde::{Deserialize, Serialize};
 
@@ -42,6 +46,11 @@ impl BotSettings {
         bot.search_depth = depth;
         bot
     }
+
+    /// the number of plies searched after each move
+    pub fn search_depth(&self) -> i32 {
+        self.search_depth
+    }
 }
 
 pub fn give_static_score(ci: &ChessInstant, mm: &MoveMap) -> i32 {
@@ -74,8 +83,8 @@ pub fn parsed_score(ci: &ChessInstant, mm: &MoveMap) -> String {
 
 pub fn bot_1_moves_default(ci: &ChessInstant, mm: &MoveMap) -> Vec<(ChessInstant, i32)> {
     let mut num_pieces = 0;
-    for (row, col) in mm.locations() {
-        let piece = ci.board_get(row, col);
+    for square in Square::all() {
+        let piece = ci.id_at(square);
         if piece != 6 {
             num_pieces += 1;
         }
@@ -94,21 +103,43 @@ pub fn bot_1_moves_default(ci: &ChessInstant, mm: &MoveMap) -> Vec<(ChessInstant
     };
 
     let bot = BotSettings::new_depth(depth);
-    return minimax(mm, &bot, ci);
+    return minimax(mm, &bot, ci, &mut Vec::new());
 }
 
 pub fn bot_1_moves(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings) -> Vec<(ChessInstant, i32)> {
-    return minimax(mm, bot, ci);
+    return minimax(mm, bot, ci, &mut Vec::new());
+}
+
+/// scores the moves for the current position of the game, repeating a position is scored as a draw
+pub fn bot_1_game_moves(game: &Game, mm: &MoveMap, bot: &BotSettings) -> Vec<(ChessInstant, i32)> {
+    let mut history = game.history_keys();
+    history.pop(); // the current position is the root of the search
+    minimax(mm, bot, game.current(), &mut history)
 }
 
-fn minimax(mm: &MoveMap, bot: &BotSettings, root: &ChessInstant) -> Vec<(ChessInstant, i32)> {
-    let l1_moves = root.valid_games(mm);
+/// history holds the keys of every position played before root
+fn minimax(
+    mm: &MoveMap,
+    bot: &BotSettings,
+    root: &ChessInstant,
+    history: &mut Vec<u64>,
+) -> Vec<(ChessInstant, i32)> {
+    let mut ci = *root;
     let mut graded = Vec::new();
-    for l1ci in l1_moves {
-        graded.push((
-            l1ci.clone(),
-            minmax_sub(&l1ci, mm, bot, bot.search_depth, false, i32::MIN, i32::MAX),
-        ));
+    for mv in root.legal_moves(mm) {
+        history.push(ci.hash_key());
+        let undo = ci.make_move(mv);
+        let score = minmax_sub(
+            &mut ci,
+            mm,
+            bot,
+            bot.search_depth,
+            false,
+            SearchState::new(history),
+        );
+        ci.unmake_move(undo);
+        history.pop();
+        graded.push((root.apply(mv), score));
     }
     graded.sort_unstable_by(|(_, a), (_, b)| b.cmp(a)); // sort max to min
 
@@ -116,14 +147,32 @@ fn minimax(mm: &MoveMap, bot: &BotSettings, root: &ChessInstant) -> Vec<(ChessIn
 
     // recursive min max fuction
     fn minmax_sub(
-        ci: &ChessInstant,
+        ci: &mut ChessInstant,
         mm: &MoveMap,
         bot: &BotSettings,
         depth: i32,
         maximizing: bool,
-        mut alpha: i32,
-        mut beta: i32,
+        mut search: SearchState,
     ) -> i32 {
+        if game::is_repeat(ci, search.history) {
+            return 0; // score for a draw
+        }
+        // legality is only worked out for the moves the search gets to before a cutoff
+        let moves = ci.pseudo_legal_moves();
+        let legality = ci.legality();
+        let has_moves = moves.iter().any(|mv| ci.is_legal_with(*mv, &legality));
+        match ci.status_with_moves(mm, has_moves) {
+            GameStatus::Ongoing => {}
+            GameStatus::Checkmate { .. } => {
+                // the player to move lost
+                if maximizing {
+                    return i32::MIN + bot.search_depth - depth;
+                } else {
+                    return i32::MAX - bot.search_depth + depth;
+                }
+            }
+            _ => return 0, // score for a draw
+        }
         if depth == 0 {
             // the heuristic value of the game
             if maximizing {
@@ -135,31 +184,41 @@ fn minimax(mm: &MoveMap, bot: &BotSettings, root: &ChessInstant) -> Vec<(ChessIn
             }
         }
 
-        let ci_children = ci.valid_games(mm);
-        if ci_children.len() == 0 && !ci.in_check(mm, &ci.king_id()){
-            return 0; // score for a draw 
-        }
         if maximizing {
             let mut value = i32::MIN; // + bot.search_depth - depth;
-            for child in ci_children {
-                let score = minmax_sub(&child, mm, bot, depth - 1, false, alpha, beta);
+            for mv in moves {
+                if !ci.is_legal_with(mv, &legality) {
+                    continue;
+                }
+                search.history.push(ci.hash_key());
+                let undo = ci.make_move(mv);
+                let score = minmax_sub(ci, mm, bot, depth - 1, false, search.child());
+                ci.unmake_move(undo);
+                search.history.pop();
                 value = value.max(score); // maximize score
-                if value > beta {
+                if value > search.beta {
                     break; // beta cutoff
                 }
 
-                alpha = if alpha > value { alpha } else { value };
+                search.alpha = search.alpha.max(value);
             }
             return value;
         } else {
             let mut value = i32::MAX; // - bot.search_depth + depth;
-            for child in ci_children {
-                let score = minmax_sub(&child, mm, bot, depth - 1, true, alpha, beta);
+            for mv in moves {
+                if !ci.is_legal_with(mv, &legality) {
+                    continue;
+                }
+                search.history.push(ci.hash_key());
+                let undo = ci.make_move(mv);
+                let score = minmax_sub(ci, mm, bot, depth - 1, true, search.child());
+                ci.unmake_move(undo);
+                search.history.pop();
                 value = value.min(score); // minimize score
-                if value < alpha {
+                if value < search.alpha {
                     break; // alpha cutoff
                 }
-                beta = if beta < value { beta } else { value };
+                search.beta = search.beta.min(value);
             }
             return value;
         }
@@ -177,11 +236,11 @@ fn static_score(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings) -> i32 {
 }
 
 /// material value of the board
-fn material(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
+fn material(ci: &ChessInstant, _mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
     let mut pieces_value = 0;
 
-    for (row, col) in mm.locations() {
-        let piece = ci.board_get(row, col);
+    for square in Square::all() {
+        let piece = ci.id_at(square);
         if piece == 6 {
             continue;
         }
@@ -208,16 +267,16 @@ fn check(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32
 }
 
 /// score for the players position pieces on the center being better
-fn position(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
+fn position(ci: &ChessInstant, _mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
     let mut position = 0;
-    for (row, col) in mm.locations() {
-        let piece = ci.board_get(row, col);
+    for square in Square::all() {
+        let piece = ci.id_at(square);
         if piece == 6 {
             continue;
         }
         // r c will give value 0-3 depening on how far from the center the location is
-        let r = 3.5 - (*row as f64 - 3.5).abs();
-        let c = 3.5 - (*col as f64 - 3.5).abs();
+        let r = 3.5 - (square.row() as f64 - 3.5).abs();
+        let c = 3.5 - (square.col() as f64 - 3.5).abs();
         if f_range.contains(&piece) {
             position += (r + c) as i32; // between 0-6 depending on how close to center
         }
@@ -229,12 +288,12 @@ fn position(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings, f_range: &Range<
 /// gives a negetive score of the piece values that are under attack
 fn attacked(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
     let mut danger = 0;
-    for (row, col) in mm.locations() {
-        let piece = ci.board_get(row, col);
+    for square in Square::all() {
+        let piece = ci.id_at(square);
         if piece == 6 {
             continue;
         }
-        if f_range.contains(&piece) && ci.threatened_dep(mm, row, col, &piece) {
+        if f_range.contains(&piece) && ci.threatened_dep(mm, square, &piece) {
             danger -= Piece::piece_value_dep(&piece, bot);
         }
     }
@@ -257,15 +316,15 @@ impl Piece {
 }
 
 impl ChessInstant {
-    fn threatened_dep(&self, mm: &MoveMap, row: &usize, col: &u32, id: &u32) -> bool {
+    fn threatened_dep(&self, mm: &MoveMap, square: Square, id: &u32) -> bool {
         if *id == 6 || *id > 12 {
             return false;
         }
 
-        let movements = mm.king_cover(&id, row, col);
+        let movements = mm.cover(&id, square);
         'paths: for path in &movements.cover {
             for (r, c, ids) in path {
-                let f = self.board_get(r, c);
+                let f = self.id_at(Square::at(*r, *c));
                 if f == 6 {
                     continue;
                 }
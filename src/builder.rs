@@ -0,0 +1,127 @@
+// this module sets up chess instants piece by piece for tests and puzzles,
+// the result is checked with validate before it is given back
+
+use crate::chess::{ChessInstant, Piece, Player};
+use crate::error::ChessError;
+use crate::square::Square;
+
+/// builds a chess instant from an empty board, king indexes are found from where the kings are put
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PositionBuilder {
+    pieces: [Option<(Player, Piece)>; 64],
+    player: Player,
+    // in the order white queen side, white king side, black queen side, black king side
+    castles: (bool, bool, bool, bool),
+    passant: Option<Square>,
+    halfmove: u16,
+    fullmove: u16,
+}
+
+impl PositionBuilder {
+    /// an empty board with white to move, no castling and no en passant
+    pub fn new() -> PositionBuilder {
+        PositionBuilder {
+            pieces: [None; 64],
+            player: Player::P1,
+            castles: (false, false, false, false),
+            passant: None,
+            halfmove: 0,
+            fullmove: 1,
+        }
+    }
+
+    /// puts the player's piece on the square, replacing whatever was there
+    pub fn put(mut self, square: Square, player: &Player, piece: &Piece) -> PositionBuilder {
+        self.pieces[square.index()] = Some((*player, *piece));
+        self
+    }
+
+    /// empties the square
+    pub fn clear(mut self, square: Square) -> PositionBuilder {
+        self.pieces[square.index()] = None;
+        self
+    }
+
+    /// sets the player to move
+    pub fn side_to_move(mut self, player: &Player) -> PositionBuilder {
+        self.player = *player;
+        self
+    }
+
+    /// sets which sides the player can still castle on, the king has to be on the e file
+    /// and the rook in the corner for each side allowed
+    pub fn castling(
+        mut self,
+        player: &Player,
+        queen_side: bool,
+        king_side: bool,
+    ) -> PositionBuilder {
+        match player {
+            Player::P1 => (self.castles.0, self.castles.1) = (queen_side, king_side),
+            Player::P2 => (self.castles.2, self.castles.3) = (queen_side, king_side),
+        }
+        self
+    }
+
+    /// sets the square a pawn skipped over with a double move on the last plie, none for no en passant
+    pub fn en_passant(mut self, square: Option<Square>) -> PositionBuilder {
+        self.passant = square;
+        self
+    }
+
+    /// sets the plies since the last capture or pawn move and the move number like in a fen
+    pub fn move_counts(mut self, halfmove: u16, fullmove: u16) -> PositionBuilder {
+        self.halfmove = halfmove;
+        self.fullmove = fullmove.clamp(1, u16::MAX / 2);
+        self
+    }
+
+    /// the chess instant for the setup, or the first reason it could not come up in a game
+    pub fn build(&self) -> Result<ChessInstant, ChessError> {
+        let mut ci = ChessInstant::new();
+        ci.prv_move = (0, 0);
+        for square in Square::all() {
+            let id = match self.pieces[square.index()] {
+                Some((player, piece)) => piece.to_id(&player),
+                None => 6,
+            };
+            ci.board_set(&square.row(), &square.col(), id);
+            match id {
+                4 => ci.p1_king = square.into(),
+                11 => ci.p2_king = square.into(),
+                _ => {}
+            }
+        }
+
+        // castling follows the standard rules, validate checks the king and rook are where they started
+        ci.valid_castles = self.castles;
+        ci.castle_cols = [0, 7, 0, 7];
+        ci.chess960 = false;
+
+        // the pawn that moved two squares belongs to the player who is not to move,
+        // validate checks it is behind one of their pawns
+        ci.p1_passant = 64;
+        ci.p2_passant = 64;
+        if let Some(square) = self.passant {
+            match self.player {
+                Player::P1 => ci.p2_passant = square.into(),
+                Player::P2 => ci.p1_passant = square.into(),
+            }
+        }
+
+        ci.halfmove = self.halfmove;
+        ci.plie = match self.player {
+            Player::P1 => self.fullmove * 2 - 1,
+            Player::P2 => self.fullmove * 2,
+        };
+        ci.hash = ci.compute_hash();
+        ci.validate()?;
+        Ok(ci)
+    }
+}
+
+impl Default for PositionBuilder {
+    fn default() -> PositionBuilder {
+        PositionBuilder::new()
+    }
+}
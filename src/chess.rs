@@ -1,10 +1,38 @@
 // this module is for finding all valid moves that could be made
 
-use crate::movemap::{MoveMap, MovePaths};
-use serde::{Deserialize, Serialize};
+use crate::bitboard::{self, Bitboards, Squares};
+use crate::error::ChessError;
+use crate::movemap::MoveMap;
+use crate::square::Square;
+use crate::zobrist;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::hash::{Hash, Hasher};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ChessInstantData", into = "ChessInstantData")]
 pub struct ChessInstant {
+    pub(crate) board: [u32; 8],
+    pub(crate) prv_move: (u8, u8),
+    pub(crate) plie: u16,
+    // plies since the last capture or pawn move
+    pub(crate) halfmove: u16,
+    pub(crate) p1_king: u8,
+    pub(crate) p2_king: u8,
+    pub(crate) p1_passant: u8,
+    pub(crate) p2_passant: u8,
+    pub(crate) valid_castles: (bool, bool, bool, bool),
+    // the column of the rook for each castle in the same order as valid_castles
+    pub(crate) castle_cols: [u32; 4],
+    pub(crate) chess960: bool,
+    // kept in step with the board by board_set
+    pub(crate) bits: Bitboards,
+    pub(crate) hash: u64,
+}
+
+/// the serialized layout of a chess instant, the bitboards are rebuilt from the board when read
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename = "ChessInstant")]
+struct ChessInstantData {
     board: [u32; 8],
     prv_move: (u8, u8),
     plie: u16,
@@ -13,6 +41,18 @@ pub struct ChessInstant {
     p1_passant: u8,
     p2_passant: u8,
     valid_castles: (bool, bool, bool, bool),
+    // older saves were made before the halfmove clock was kept
+    #[serde(default)]
+    halfmove: u16,
+    #[serde(default = "standard_castle_cols")]
+    castle_cols: [u32; 4],
+    #[serde(default)]
+    chess960: bool,
+}
+
+/// the rook columns for castling in a standard game
+fn standard_castle_cols() -> [u32; 4] {
+    [0, 7, 0, 7]
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -31,6 +71,31 @@ pub enum Piece {
     King,
 }
 
+/// what make_move changed that can not be worked out from the move, used to unmake the move
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Undo {
+    mv: Move,
+    prv_move: (u8, u8),
+    p1_passant: u8,
+    p2_passant: u8,
+    valid_castles: (bool, bool, bool, bool),
+    halfmove: u16,
+    hash: u64,
+}
+
+/// a single move, castling is given as the king moving two spaces
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub piece: Piece,
+    pub captured: Option<Piece>,
+    pub promotion: Option<Piece>,
+    pub castle: bool,
+    pub en_passant: bool,
+    pub double_push: bool,
+}
+
 // values for pieces are such
 // player 1: 0-5
 // no piece: 6
@@ -63,34 +128,57 @@ impl ChessInstant {
     }
 
     /// determimes if the current player is in check, pice id is any friendly id of the king you are checking for ckeck
-    pub fn in_check(&self, mm: &MoveMap, piece_id: &u32) -> bool {
-        let (kr, kc) = if *piece_id < 6 {
-            ChessInstant::decode_index(&self.p1_king)
+    pub fn in_check(&self, _mm: &MoveMap, piece_id: &u32) -> bool {
+        if *piece_id < 6 {
+            self.square_attacked(self.p1_king as usize, &Player::P2)
         } else {
-            ChessInstant::decode_index(&self.p2_king)
-        };
-        let kcover = mm.king_cover(piece_id, &kr, &kc);
+            self.square_attacked(self.p2_king as usize, &Player::P1)
+        }
+    }
 
-        'paths: for path in &kcover.cover {
-            for (r, c, atk) in path {
-                let fpiece = self.board_get(r, c);
-                if self.is_friendly(piece_id, &fpiece) {
-                    continue 'paths;
-                }
-                if atk.contains(&fpiece) {
-                    return true;
-                }
-                if fpiece != 6 {
-                    continue 'paths;
-                }
-            }
+    /// determines if any piece of the attacking player could take on the square index
+    pub(crate) fn square_attacked(&self, square: usize, attacker: &Player) -> bool {
+        self.attackers_to(square, self.bits.occupied()) & self.bits.player(attacker) != 0
+    }
+
+    /// the number of plies since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> u16 {
+        self.halfmove
+    }
+
+    /// fifty moves by each player without a capture or pawn move, a draw if a player claims it
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove >= 100
+    }
+
+    /// seventy five moves by each player without a capture or pawn move, the game is drawn without a claim
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.halfmove >= 150
+    }
+
+    /// neither player has the pieces to checkmate: king against king, king and a bishop or
+    /// knight against king, or kings and bishops where every bishop is on the same colour
+    pub fn is_insufficient_material(&self) -> bool {
+        let pieces = |piece: Piece| {
+            self.bits.pieces(piece.to_id(&Player::P1)) | self.bits.pieces(piece.to_id(&Player::P2))
+        };
+        if pieces(Piece::Pawn) | pieces(Piece::Rook) | pieces(Piece::Queen) != 0 {
+            return false;
         }
-        false
+        let knights = pieces(Piece::Knight);
+        let bishops = pieces(Piece::Bishop);
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+        knights == 0
+            && (bishops & bitboard::LIGHT_SQUARES == 0 || bishops & !bitboard::LIGHT_SQUARES == 0)
     }
 
     /// DO NOT USE TO APART FROM ANALASIS. adds one to the plie will mess up and game you try and play after
     pub fn add_plie(&mut self) {
+        self.hash ^= self.state_key();
         self.plie += 1;
+        self.hash ^= self.state_key();
     }
 
     /// compair against another piece and determine if they are friendly with eachother
@@ -104,23 +192,39 @@ impl ChessInstant {
         false
     }
 
-    /// every friendly piece that is covering the piece at the given location
+    /// every friendly piece that is covering the piece at the given location,
+    /// pieces pinned to their king off the line through the location can not take back so are left out
+    #[deprecated(note = "use protectors with a Square")]
     pub fn protected_by(&self, row: &usize, col: &u32, mm: &MoveMap) -> Vec<u32> {
-        let piece = self.board_get(row, col);
+        self.protectors(
+            Square::from_row_col(*row, *col).expect("row and col must be less than 8"),
+            mm,
+        )
+    }
+
+    /// every friendly piece that is covering the piece on the square,
+    /// pieces pinned to their king off the line through the square can not take back so are left out
+    pub fn protectors(&self, square: Square, mm: &MoveMap) -> Vec<u32> {
+        let piece = self.id_at(square);
         if piece == 6 || piece > 12 {
             return vec![];
         }
         let tmp_id = if piece < 6 { piece + 7 } else { piece - 7 };
+        let player = if piece < 6 { Player::P1 } else { Player::P2 };
+        let pins = self.pinned_pieces(&player);
 
-        let movements = mm.king_cover(&tmp_id, row, col);
+        let movements = mm.cover(&tmp_id, square);
         let mut friends = Vec::new();
         'paths: for path in &movements.cover {
             for (r, c, ids) in path {
-                let f = self.board_get(r, c);
+                let f = self.id_at(Square::at(*r, *c));
                 if f == 6 {
                     continue;
                 }
-                if self.is_friendly(&piece, &f) && ids.contains(&f) {
+                let pinned_away = pins
+                    .iter()
+                    .any(|pin| pin.pinned == Square::at(*r, *c) && !pin.line.contains(square));
+                if self.is_friendly(&piece, &f) && ids.contains(&f) && !pinned_away {
                     friends.push(f);
                 }
                 continue 'paths;
@@ -130,16 +234,25 @@ impl ChessInstant {
         friends
     }
     /// every opponent piece (id) that could take the piece at given row and column
+    #[deprecated(note = "use threats_to with a Square")]
     pub fn threatened_by(&self, row: &usize, col: &u32, mm: &MoveMap) -> Vec<u32> {
-        let piece = self.board_get(row, col);
+        self.threats_to(
+            Square::from_row_col(*row, *col).expect("row and col must be less than 8"),
+            mm,
+        )
+    }
+
+    /// every opponent piece (id) that could take the piece on the square
+    pub fn threats_to(&self, square: Square, mm: &MoveMap) -> Vec<u32> {
+        let piece = self.id_at(square);
         if piece == 6 || piece > 12 {
             return vec![];
         }
-        let movements = mm.king_cover(&piece, row, col);
+        let movements = mm.cover(&piece, square);
         let mut threats = Vec::new();
         'paths: for path in &movements.cover {
             for (r, c, ids) in path {
-                let f = self.board_get(r, c);
+                let f = self.id_at(Square::at(*r, *c));
                 if f == 6 {
                     continue;
                 }
@@ -153,18 +266,27 @@ impl ChessInstant {
         threats
     }
     // every opponent id that is in the path blocked or not of the piece at the given location
+    #[deprecated(note = "use in_path_of with a Square")]
     pub fn in_path(&self, row: &usize, col: &u32, mm: &MoveMap) -> Vec<u32> {
-        let piece = self.board_get(row, col);
+        self.in_path_of(
+            Square::from_row_col(*row, *col).expect("row and col must be less than 8"),
+            mm,
+        )
+    }
+
+    /// every opponent id that is in the path blocked or not of the piece on the square
+    pub fn in_path_of(&self, square: Square, mm: &MoveMap) -> Vec<u32> {
+        let piece = self.id_at(square);
         let pawn;
         let movements = match piece {
-            0 | 7 => &mm.get_rook_moves(row, col).paths,
-            1 | 8 => &mm.get_knight_moves(row, col).paths,
-            2 | 9 => &mm.get_bishop_moves(row, col).paths,
-            3 | 10 => &mm.get_queen_moves(row, col).paths,
-            4 | 11 => &mm.get_king_moves(row, col).paths,
+            0 | 7 => &mm.rook_moves(square).paths,
+            1 | 8 => &mm.knight_moves(square).paths,
+            2 | 9 => &mm.bishop_moves(square).paths,
+            3 | 10 => &mm.queen_moves(square).paths,
+            4 | 11 => &mm.king_moves(square).paths,
             5 | 12 => {
                 pawn = mm
-                    .get_pawn_moves(row, col, &piece)
+                    .pawn_moves(square, &piece)
                     .atk
                     .iter()
                     .map(|p| vec![p.clone()])
@@ -176,7 +298,7 @@ impl ChessInstant {
         let mut found = Vec::new();
         for path in movements {
             for (r, c) in path {
-                let loc = self.board_get(r, c);
+                let loc = self.id_at(Square::at(*r, *c));
                 if loc == 6 {
                     continue;
                 }
@@ -188,18 +310,27 @@ impl ChessInstant {
         found
     }
     /// gives back a list of all the piece ids that the piece at that location can hit
+    #[deprecated(note = "use targets with a Square")]
     pub fn threatens(&self, row: &usize, col: &u32, mm: &MoveMap) -> Vec<u32> {
-        let piece = self.board_get(row, col);
+        self.targets(
+            Square::from_row_col(*row, *col).expect("row and col must be less than 8"),
+            mm,
+        )
+    }
+
+    /// gives back a list of all the piece ids that the piece on the square can hit
+    pub fn targets(&self, square: Square, mm: &MoveMap) -> Vec<u32> {
+        let piece = self.id_at(square);
         let pawn;
         let movements = match piece {
-            0 | 7 => &mm.get_rook_moves(row, col).paths,
-            1 | 8 => &mm.get_knight_moves(row, col).paths,
-            2 | 9 => &mm.get_bishop_moves(row, col).paths,
-            3 | 10 => &mm.get_queen_moves(row, col).paths,
-            4 | 11 => &mm.get_king_moves(row, col).paths,
+            0 | 7 => &mm.rook_moves(square).paths,
+            1 | 8 => &mm.knight_moves(square).paths,
+            2 | 9 => &mm.bishop_moves(square).paths,
+            3 | 10 => &mm.queen_moves(square).paths,
+            4 | 11 => &mm.king_moves(square).paths,
             5 | 12 => {
                 pawn = mm
-                    .get_pawn_moves(row, col, &piece)
+                    .pawn_moves(square, &piece)
                     .atk
                     .iter()
                     .map(|p| vec![p.clone()])
@@ -211,7 +342,7 @@ impl ChessInstant {
         let mut found = Vec::new();
         'path: for path in movements {
             for (r, c) in path {
-                let loc = self.board_get(r, c);
+                let loc = self.id_at(Square::at(*r, *c));
                 if loc == 6 {
                     continue;
                 }
@@ -223,9 +354,16 @@ impl ChessInstant {
         }
         found
     }
+
     /// returns the option for the player and piece for given row and col
+    #[deprecated(note = "use piece_at with a Square")]
     pub fn player_piece(&self, row: &usize, col: &u32) -> Option<(Player, Piece)> {
-        let piece_id = self.board_get(row, col);
+        self.piece_at(Square::from_row_col(*row, *col).expect("row and col must be less than 8"))
+    }
+
+    /// the player and piece on the square, none when it is empty
+    pub fn piece_at(&self, square: Square) -> Option<(Player, Piece)> {
+        let piece_id = self.id_at(square);
         if piece_id == 6 {
             return None;
         }
@@ -243,7 +381,7 @@ impl ChessInstant {
         for r in 0..8 {
             let mut row = Vec::new();
             for c in 0..8 {
-                row.push(self.player_piece(&r, &c));
+                row.push(self.piece_at(Square::at(r, c)));
             }
             board.push(row);
         }
@@ -253,453 +391,411 @@ impl ChessInstant {
 
     /// returns all valid chess instants that can result from self
     pub fn valid_games(&self, mm: &MoveMap) -> Vec<ChessInstant> {
+        let king_id = self.king_id();
+        self.pseudo_legal_moves()
+            .into_iter()
+            .map(|mv| self.apply(mv))
+            .filter(|next| !next.in_check(mm, &king_id))
+            .collect()
+    }
+
+    /// returns every legal move that can be made from self
+    pub fn legal_moves(&self, _mm: &MoveMap) -> Vec<Move> {
+        let mut moves = self.pseudo_legal_moves();
+        self.retain_legal(&mut moves);
+        moves
+    }
+
+    /// makes the move and gives back the resulting chess instant,
+    /// the move is not checked so it should come from legal_moves
+    pub fn apply(&self, mv: Move) -> ChessInstant {
         let mut ci = *self;
-        ci.reset_en_passant();
-        let mut found_valid: Vec<ChessInstant> = Vec::with_capacity(50);
-        let player_range = self.player_range();
-        for (row, col) in mm.locations() {
-            let piece = self.board_get(&row, &col);
-            if !player_range.contains(&piece) {
-                continue;
-            }
-            match piece {
-                0 | 7 => {
-                    ci.rook_movement(&mut found_valid, &row, &col, mm, &piece);
-                    ci.castling_movement(&mut found_valid, &row, &col, mm, &piece);
-                }
-                1 | 8 => ci.standard_movement(
-                    mm.get_knight_moves(&row, &col),
-                    &mut found_valid,
-                    &row,
-                    &col,
-                    mm,
-                    &piece,
-                ),
-                2 | 9 => ci.standard_movement(
-                    mm.get_bishop_moves(&row, &col),
-                    &mut found_valid,
-                    &row,
-                    &col,
-                    mm,
-                    &piece,
-                ),
-                3 | 10 => ci.standard_movement(
-                    mm.get_queen_moves(&row, &col),
-                    &mut found_valid,
-                    &row,
-                    &col,
-                    mm,
-                    &piece,
-                ),
-                4 | 11 => ci.king_movement(&mut found_valid, &row, &col, mm, &piece),
-                5 | 12 => ci.pawn_movement(&mut found_valid, &row, &col, mm, &piece), // pawn movement,
-                _ => continue,
-            }
-        }
-        found_valid
+        ci.make_move(mv);
+        ci
     }
 
-    /// check validity and add a castling move
-    fn castling_movement(
-        &self,
-        valid: &mut Vec<ChessInstant>,
-        row: &usize,
-        col: &u32,
-        mm: &MoveMap,
-        piece_id: &u32,
-    ) {
-        // check if valid to make castle move
-        if *piece_id < 6 && !self.valid_castles.0 && !self.valid_castles.1 {
-            return;
-        }
-        if *piece_id > 6 && !self.valid_castles.2 && !self.valid_castles.3 {
-            return;
-        }
-        if self.in_check(mm, piece_id) {
-            return;
+    /// makes the move in place, the returned undo takes the game back with unmake_move.
+    /// the move is not checked so it should come from legal_moves
+    pub fn make_move(&mut self, mv: Move) -> Undo {
+        let undo = Undo {
+            mv,
+            prv_move: self.prv_move,
+            p1_passant: self.p1_passant,
+            p2_passant: self.p2_passant,
+            valid_castles: self.valid_castles,
+            halfmove: self.halfmove,
+            hash: self.hash,
+        };
+        // the key for the player, castles and passant is added back once they are updated
+        self.hash ^= self.state_key();
+        let player = self.player();
+        let piece_id = mv.piece.to_id(&player);
+        let (fr, fc): (usize, u32) = mv.from.into();
+        let (tr, tc): (usize, u32) = mv.to.into();
+
+        self.reset_en_passant();
+        self.board_set(&fr, &fc, 6); // set from to blank value
+        if mv.castle {
+            // the rook jumps to the other side of the king, it is moved first
+            // as in chess960 the king can land on the square the rook started on
+            let (rook_from, rook_to) = self.castle_rook_cols(&player, &tc);
+            self.board_set(&tr, &rook_from, 6);
+            self.board_set(&tr, &rook_to, Piece::Rook.to_id(&player));
         }
+        let placed = match mv.promotion {
+            Some(promotion) => promotion.to_id(&player),
+            None => piece_id,
+        };
+        self.board_set(&tr, &tc, placed); // set to to piece id
 
-        // player 1 left
-        if *row == 7 && *col == 0 {
-            let (krow, kcol) = ChessInstant::decode_index(&self.p1_king);
-            let movement = vec![(7, 3), (7, 2)];
-            make_castle_move(valid, *self, mm, &movement, &krow, &kcol, &4, row, col, 0);
-        }
-        // player 1 right
-        if *row == 7 && *col == 7 {
-            let (krow, kcol) = ChessInstant::decode_index(&self.p1_king);
-            let movement = vec![(7, 5), (7, 6)];
-            make_castle_move(valid, *self, mm, &movement, &krow, &kcol, &4, row, col, 0);
-        }
-
-        // player 2 left
-        if *row == 0 && *col == 0 {
-            let (krow, kcol) = ChessInstant::decode_index(&self.p2_king);
-            let movement = vec![(0, 3), (0, 2)];
-            make_castle_move(valid, *self, mm, &movement, &krow, &kcol, &11, row, col, 7);
-        }
-        // player 2 right
-        if *row == 0 && *col == 7 {
-            let (krow, kcol) = ChessInstant::decode_index(&self.p2_king);
-            let movement = vec![(0, 5), (0, 6)];
-            make_castle_move(valid, *self, mm, &movement, &krow, &kcol, &11, row, col, 7);
-        }
-
-        fn make_castle_move(
-            valid: &mut Vec<ChessInstant>,
-            mut game: ChessInstant,
-            mm: &MoveMap,
-            movement: &Vec<(usize, u32)>,
-            row: &usize,
-            col: &u32,
-            piece_id: &u32,
-            rookr: &usize,
-            rookc: &u32,
-            rookid: u32,
-        ) {
-            for (r, c) in movement {
-                if game.board_get(r, c) != 6 {
-                    return; // if any movespace is occupied not valid castle
-                }
-                // check the left castle extra space
-                if *c == 2 {
-                    if game.board_get(row, &1) != 6 {
-                        return;
-                    }
-                }
+        if mv.en_passant {
+            self.board_set(&fr, &tc, 6); // take the piece en passant
+        }
+        if mv.double_push {
+            let passant = Square::at((fr + tr) / 2, fc).into();
+            match player {
+                Player::P1 => self.p1_passant = passant,
+                Player::P2 => self.p2_passant = passant,
             }
-
-            let (mut prvr, mut prvc) = (*row, *col);
-            for (r, c) in movement {
-                // move the king
-                game.board_set(&prvr, &prvc, 6);
-                game.board_set(&r, &c, *piece_id);
-                // move king location
-                if *piece_id < 6 {
-                    game.p1_king = ChessInstant::encode_index(r, c);
-                } else {
-                    game.p2_king = ChessInstant::encode_index(r, c);
+        }
+        if mv.piece == Piece::King {
+            // move stored king location, castling no longer valid for the player
+            match player {
+                Player::P1 => {
+                    self.p1_king = Square::at(tr, tc).into();
+                    self.valid_castles.0 = false;
+                    self.valid_castles.1 = false;
                 }
-                // return if in check at any point
-                if game.in_check(mm, piece_id) {
-                    return;
+                Player::P2 => {
+                    self.p2_king = Square::at(tr, tc).into();
+                    self.valid_castles.2 = false;
+                    self.valid_castles.3 = false;
                 }
-                // update prevous row and col
-                (prvr, prvc) = (*r, *c);
             }
-
-            if *piece_id < 6 {
-                game.valid_castles.0 = false;
-                game.valid_castles.1 = false;
-            } else {
-                game.valid_castles.2 = false;
-                game.valid_castles.3 = false;
+        }
+        // moving a rook off or capturing a rook on its starting square removes that castle
+        let cols = self.castle_cols;
+        for (r, c) in [mv.from.into(), mv.to.into()] {
+            match (r, c) {
+                (7, c) if c == cols[0] => self.valid_castles.0 = false,
+                (7, c) if c == cols[1] => self.valid_castles.1 = false,
+                (0, c) if c == cols[2] => self.valid_castles.2 = false,
+                (0, c) if c == cols[3] => self.valid_castles.3 = false,
+                _ => (),
             }
+        }
 
-            // move the rook
-            game.board_set(rookr, rookc, 6);
-            game.board_set(&movement[0].0, &movement[0].1, rookid);
-            game.prv_move = (
-                ChessInstant::encode_index(row, col),
-                ChessInstant::encode_index(&prvr, &prvc),
-            );
-            game.plie += 1;
-            // add the valid castle move
-            valid.push(game);
+        // captures and pawn moves reset the clock for the fifty move rule
+        if mv.captured.is_some() || mv.piece == Piece::Pawn {
+            self.halfmove = 0;
+        } else {
+            self.halfmove += 1;
         }
+        self.plie += 1;
+        self.prv_move = (Square::at(fr, fc).into(), Square::at(tr, tc).into());
+        self.hash ^= self.state_key();
+        undo
     }
 
-    /// handle movement for rook, knight, bishop,queen, and king
-    fn standard_movement(
-        &self,
-        movements: &MovePaths,
-        valid: &mut Vec<ChessInstant>,
-        row: &usize,
-        col: &u32,
-        mm: &MoveMap,
-        piece_id: &u32,
-    ) {
-        //let movements = mm.get_standard_moves(row, col, piece_id);
-        'paths: for path in &movements.paths {
-            for (tr, tc) in path {
-                let found = self.board_get(tr, tc);
-                if self.is_friendly(piece_id, &found) {
-                    continue 'paths;
-                }
-                self.make_move_standard(mm, valid, piece_id, row, col, tr, tc);
-                if found != 6 {
-                    continue 'paths;
-                }
+    /// takes back a move made by make_move, undos have to be given back in the reverse order they were made
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let mv = undo.mv;
+        self.plie -= 1;
+        let player = self.player();
+        let (fr, fc): (usize, u32) = mv.from.into();
+        let (tr, tc): (usize, u32) = mv.to.into();
+
+        let captured = match mv.captured {
+            Some(captured) => captured.to_id(&player.swap()),
+            None => 6,
+        };
+        if mv.castle {
+            // both pieces are taken off before either is put back as their squares can overlap in chess960
+            let (rook_from, rook_to) = self.castle_rook_cols(&player, &tc);
+            self.board_set(&tr, &tc, 6);
+            self.board_set(&tr, &rook_to, 6);
+            self.board_set(&tr, &rook_from, Piece::Rook.to_id(&player));
+            self.board_set(&fr, &fc, mv.piece.to_id(&player));
+        } else if mv.en_passant {
+            self.board_set(&fr, &fc, mv.piece.to_id(&player));
+            self.board_set(&tr, &tc, 6);
+            self.board_set(&fr, &tc, captured);
+        } else {
+            self.board_set(&fr, &fc, mv.piece.to_id(&player));
+            self.board_set(&tr, &tc, captured);
+        }
+        if mv.piece == Piece::King {
+            match player {
+                Player::P1 => self.p1_king = Square::at(fr, fc).into(),
+                Player::P2 => self.p2_king = Square::at(fr, fc).into(),
             }
         }
+
+        self.prv_move = undo.prv_move;
+        self.p1_passant = undo.p1_passant;
+        self.p2_passant = undo.p2_passant;
+        self.valid_castles = undo.valid_castles;
+        self.halfmove = undo.halfmove;
+        self.hash = undo.hash;
     }
 
-    /// handle movement for the king
-    fn king_movement(
-        &self,
-        valid: &mut Vec<ChessInstant>,
-        row: &usize,
-        col: &u32,
-        mm: &MoveMap,
-        piece_id: &u32,
-    ) {
-        let movements = mm.get_king_moves(row, col);
-        'paths: for path in &movements.paths {
-            for (tr, tc) in path {
-                let found = self.board_get(tr, tc);
-                if self.is_friendly(piece_id, &found) {
-                    continue 'paths;
-                }
-                self.make_move_king(mm, valid, piece_id, row, col, tr, tc);
-                if found != 6 {
-                    continue 'paths;
-                }
-            }
+    /// the columns the rook moves from and to when the player's king castles to the given column
+    pub(crate) fn castle_rook_cols(&self, player: &Player, king_col: &u32) -> (u32, u32) {
+        let side = bitboard::side(player) * 2;
+        if *king_col == 2 {
+            (self.castle_cols[side], 3)
+        } else {
+            (self.castle_cols[side + 1], 5)
         }
     }
 
-    /// handle movement for the rook
-    fn rook_movement(
-        &self,
-        valid: &mut Vec<ChessInstant>,
-        row: &usize,
-        col: &u32,
-        mm: &MoveMap,
-        piece_id: &u32,
-    ) {
-        let movements = mm.get_rook_moves(row, col);
-        'paths: for path in &movements.paths {
-            for (tr, tc) in path {
-                let found = self.board_get(tr, tc);
-                if self.is_friendly(piece_id, &found) {
-                    continue 'paths;
-                }
-                self.make_move_rook(mm, valid, piece_id, row, col, tr, tc);
-                if found != 6 {
-                    continue 'paths;
-                }
-            }
-        }
+    /// the game is played with the chess960 castling rules
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
     }
 
-    /// handle all pawn movement
-    fn pawn_movement(
-        &self,
-        valid: &mut Vec<ChessInstant>,
-        row: &usize,
-        col: &u32,
-        mm: &MoveMap,
-        piece_id: &u32,
-    ) {
-        let movements = mm.get_pawn_moves(row, col, piece_id);
-        // forwayd movement
-        for (i, (r, c)) in movements.mov.iter().enumerate() {
-            let found = self.board_get(r, c);
-            if found != 6 {
-                break; // can only move onto a blank space
-            }
-            if i == 1 {
-                self.make_move_double_pawn(mm, valid, piece_id, row, col, r, c);
-            } else {
-                if *r == 0 || *r == 7 {
-                    // if on end row promote that pawn!
-                    let ids = if *piece_id < 6 {
-                        vec![0, 1, 2, 3]
-                    } else {
-                        vec![7, 8, 9, 10]
-                    };
-                    for id in ids {
-                        self.make_move_standard(mm, valid, &id, row, col, r, c);
-                    }
-                } else {
-                    self.make_move_standard(mm, valid, piece_id, row, col, r, c);
-                }
+    /// every move the current player could make without checking if it leaves their king in check,
+    /// is_legal tells which of them can be played
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        self.generate_moves(true, true, !0)
+    }
+
+    /// the legal captures and promotions, en passant included, the moves a quiescence search looks at
+    pub fn capture_moves(&self) -> Vec<Move> {
+        let mut moves = self.generate_moves(true, false, !0);
+        self.retain_legal(&mut moves);
+        moves
+    }
+
+    /// the legal moves that neither capture nor promote, castling included
+    pub fn quiet_moves(&self) -> Vec<Move> {
+        let mut moves = self.generate_moves(false, true, !0);
+        self.retain_legal(&mut moves);
+        moves
+    }
+
+    /// the legal moves out of check, empty when the current player is not in check
+    pub fn evasion_moves(&self) -> Vec<Move> {
+        let player = self.player();
+        let checkers = self.checker_bits(&player);
+        let targets = match checkers.count_ones() {
+            0 => return Vec::new(),
+            // pieces other than the king have to take the checker or get in the way
+            1 => {
+                let checker = checkers.trailing_zeros() as usize;
+                bitboard::between(self.king_square(&player), checker) | checkers
             }
+            // only the king can move out of a double check
+            _ => 0,
+        };
+        let mut moves = self.generate_moves(true, true, targets);
+        self.retain_legal(&mut moves);
+        moves
+    }
+
+    /// the pseudo legal captures and promotions, the quiet moves or both, pieces other than
+    /// the king only move to squares in targets
+    fn generate_moves(&self, captures: bool, quiets: bool, targets: u64) -> Vec<Move> {
+        let mut found: Vec<Move> = Vec::with_capacity(50);
+        let player = self.player();
+        let occupied = self.bits.occupied();
+        let mut kinds = 0;
+        if captures {
+            kinds |= self.bits.player(&player.swap());
         }
-        // attacking movement
-        for (r, c) in &movements.atk {
-            let found = self.board_get(r, c);
-            if found == 6 || self.is_friendly(piece_id, &found) {
-                continue;
-            }
-            if *r == 0 || *r == 7 {
-                // if on end row promote that pawn!
-                let ids = if *piece_id < 6 {
-                    vec![0, 1, 2, 3]
+        if quiets {
+            kinds |= !occupied;
+        }
+        for piece in [
+            Piece::Rook,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Queen,
+            Piece::King,
+        ] {
+            let piece_id = piece.to_id(&player);
+            for from in Squares(self.bits.pieces(piece_id)) {
+                let attacks = match piece {
+                    Piece::Rook => bitboard::rook_attacks(from, occupied),
+                    Piece::Knight => bitboard::KNIGHT_ATTACKS[from],
+                    Piece::Bishop => bitboard::bishop_attacks(from, occupied),
+                    Piece::Queen => {
+                        bitboard::rook_attacks(from, occupied)
+                            | bitboard::bishop_attacks(from, occupied)
+                    }
+                    _ => bitboard::KING_ATTACKS[from],
+                };
+                let allowed = if piece == Piece::King {
+                    kinds
                 } else {
-                    vec![7, 8, 9, 10]
+                    kinds & targets
                 };
-                for id in ids {
-                    self.make_move_standard(mm, valid, &id, row, col, r, c);
-                }
-            } else {
-                self.make_move_standard(mm, valid, piece_id, row, col, r, c);
+                self.standard_movement(&mut found, &piece_id, from, attacks & allowed);
             }
         }
-        // en passant
-        let ep = if *piece_id < 6 {
-            &self.p2_passant
-        } else {
-            &self.p1_passant
+        self.pawn_movement(&mut found, &player, captures, quiets, targets);
+        if quiets {
+            self.castling_movement(&mut found, &player);
+        }
+        found
+    }
+
+    /// check validity and add the castling moves for the player, the king always ends on
+    /// column 2 or 6 and the rook next to it on 3 or 5 from wherever they started
+    fn castling_movement(&self, valid: &mut Vec<Move>, player: &Player) {
+        let c = self.castle_cols;
+        let (row, king, rights, cols) = match player {
+            Player::P1 => (
+                7,
+                self.p1_king,
+                [self.valid_castles.0, self.valid_castles.1],
+                [c[0], c[1]],
+            ),
+            Player::P2 => (
+                0,
+                self.p2_king,
+                [self.valid_castles.2, self.valid_castles.3],
+                [c[2], c[3]],
+            ),
         };
-        if *ep > 63 {
-            return; // not a valid row and col
+        let king = king as usize;
+        let king_col = (king % 8) as u32;
+        if king / 8 != row || !(rights[0] || rights[1]) {
+            return;
         }
-        let (epr, epc) = ChessInstant::decode_index(ep);
-        if movements.atk.contains(&(epr, epc)) {
-            self.make_move_en_passant(mm, valid, piece_id, row, col, &epr, &epc);
+        let attacker = player.swap();
+        if self.square_attacked(king, &attacker) {
+            return;
         }
-    }
 
-    /// moves piece at from to to then checks if king is in check if not adds new game indtance to valid
-    fn make_move_rook(
-        &self,
-        mm: &MoveMap,
-        valid: &mut Vec<ChessInstant>,
-        piece_id: &u32,
-        from_row: &usize,
-        from_col: &u32,
-        to_row: &usize,
-        to_col: &u32,
-    ) {
-        let mut clone = *self;
-        clone.board_set(from_row, from_col, 6); // set from to blank value
-        clone.board_set(to_row, to_col, *piece_id); // set to to piece id
-        if clone.in_check(mm, piece_id) {
-            return; // not valid if in check
-        }
-        if *from_row == 7 && *from_col == 0 {
-            clone.valid_castles.0 = false;
-        } else if *from_row == 7 && *from_col == 7 {
-            clone.valid_castles.1 = false;
-        } else if *from_row == 0 && *from_col == 0 {
-            clone.valid_castles.2 = false;
-        } else if *from_row == 0 && *from_col == 7 {
-            clone.valid_castles.3 = false;
-        }
-
-        clone.plie += 1;
-        clone.prv_move = (
-            ChessInstant::encode_index(from_row, from_col),
-            ChessInstant::encode_index(to_row, to_col),
-        );
-        valid.push(clone);
-    }
-
-    /// moves piece at from to to then checks if king is in check if not adds new game indtance to valid
-    fn make_move_king(
-        &self,
-        mm: &MoveMap,
-        valid: &mut Vec<ChessInstant>,
-        piece_id: &u32,
-        from_row: &usize,
-        from_col: &u32,
-        to_row: &usize,
-        to_col: &u32,
-    ) {
-        let mut clone = *self;
-        clone.board_set(from_row, from_col, 6); // set from to blank value
-        clone.board_set(to_row, to_col, *piece_id); // set to to piece id
-                                                    // move stored king location
-        if *piece_id < 6 {
-            clone.p1_king = ChessInstant::encode_index(to_row, to_col);
-            clone.valid_castles.0 = false; // castling no longer valid for player 1
-            clone.valid_castles.1 = false;
-        } else {
-            clone.p2_king = ChessInstant::encode_index(to_row, to_col);
-            clone.valid_castles.2 = false; // castling no longer valid for player 2
-            clone.valid_castles.3 = false;
+        let rooks = self.bits.pieces(Piece::Rook.to_id(player));
+        let occupied = self.bits.occupied();
+        // (valid, rook column, king destination, rook destination)
+        let sides = [(rights[0], cols[0], 2, 3), (rights[1], cols[1], 6, 5)];
+        for (valid_side, rook_col, king_to, rook_to) in sides {
+            let rook = 1 << (row * 8 + rook_col as usize);
+            // the rook has to be on the side of the king it castles to
+            if !valid_side || rooks & rook == 0 || (king_to == 2) != (rook_col < king_col) {
+                continue;
+            }
+            // every square the king or rook crosses has to be empty apart from the two of them
+            let crossed = rank_span(row, king_col, king_to) | rank_span(row, rook_col, rook_to);
+            if occupied & crossed & !(rook | 1 << king) != 0 {
+                continue; // if any movespace is occupied not valid castle
+            }
+            // the king can not move through check
+            if Squares(rank_span(row, king_col, king_to) & !(1 << king))
+                .any(|square| self.square_attacked(square, &attacker))
+            {
+                continue;
+            }
+            valid.push(Move {
+                from: Square::at_index(king),
+                to: Square::at(row, king_to),
+                piece: Piece::King,
+                captured: None,
+                promotion: None,
+                castle: true,
+                en_passant: false,
+                double_push: false,
+            });
         }
-        if clone.in_check(mm, piece_id) {
-            return; // not valid if in check
+    }
+
+    /// adds a move from the square index to every square index in targets
+    fn standard_movement(&self, valid: &mut Vec<Move>, piece_id: &u32, from: usize, targets: u64) {
+        let from = Square::at_index(from);
+        for to in Squares(targets) {
+            let to = Square::at_index(to);
+            valid.push(Move::new(piece_id, from, to, &self.id_at(to)));
         }
-        clone.plie += 1;
-        clone.prv_move = (
-            ChessInstant::encode_index(from_row, from_col),
-            ChessInstant::encode_index(to_row, to_col),
-        );
-        valid.push(clone);
     }
 
-    /// handles logic for the en passant attack
-    fn make_move_en_passant(
-        &self,
-        mm: &MoveMap,
-        valid: &mut Vec<ChessInstant>,
-        piece_id: &u32,
-        from_row: &usize,
-        from_col: &u32,
-        to_row: &usize,
-        to_col: &u32,
-    ) {
-        let mut clone = *self;
-        clone.board_set(from_row, from_col, 6); // set from to blank value
-        clone.board_set(to_row, to_col, *piece_id); // set to to piece id
-        clone.board_set(from_row, to_col, 6); // take the piece en passant
-        if clone.in_check(mm, piece_id) {
-            return; // not valid if in check
-        }
-        clone.plie += 1;
-        clone.prv_move = (
-            ChessInstant::encode_index(from_row, from_col),
-            ChessInstant::encode_index(to_row, to_col),
-        );
-        valid.push(clone);
-    }
-
-    /// does the same as standard move but sets the en passant value
-    fn make_move_double_pawn(
+    /// handle the pawn movement for the player, pushes onto the end row are promotions so are
+    /// added with the captures
+    fn pawn_movement(
         &self,
-        mm: &MoveMap,
-        valid: &mut Vec<ChessInstant>,
-        piece_id: &u32,
-        from_row: &usize,
-        from_col: &u32,
-        to_row: &usize,
-        to_col: &u32,
+        valid: &mut Vec<Move>,
+        player: &Player,
+        captures: bool,
+        quiets: bool,
+        targets: u64,
     ) {
-        let mut clone = *self;
-        clone.board_set(from_row, from_col, 6); // set from to blank value
-        clone.board_set(to_row, to_col, *piece_id); // set to to piece id
-        if clone.in_check(mm, piece_id) {
-            return; // not valid if in check
-        }
-        clone.plie += 1;
-        if *piece_id < 6 {
-            clone.p1_passant = ChessInstant::encode_index(&(from_row - 1), from_col);
-        } else {
-            clone.p2_passant = ChessInstant::encode_index(&(from_row + 1), from_col);
+        let piece_id = Piece::Pawn.to_id(player);
+        let side = bitboard::side(player);
+        let enemy = self.bits.player(&player.swap());
+        let empty = !self.bits.occupied();
+        // en passant can only be taken on the passant of the player that just moved
+        let passant = match player {
+            Player::P1 => self.p2_passant,
+            Player::P2 => self.p1_passant,
+        };
+        let passant = if passant < 64 { 1 << passant } else { 0 };
+
+        for from in Squares(self.bits.pieces(piece_id)) {
+            let from_square = Square::at_index(from);
+            // forward movement, player 1 moves toward row 0
+            let (one, start_row) = match player {
+                Player::P1 => (from.wrapping_sub(8), 6),
+                Player::P2 => (from + 8, 1),
+            };
+            if one < 64 && empty & 1 << one != 0 {
+                let (to_row, _) = bitboard::row_col(one);
+                let wanted = if to_row == 0 || to_row == 7 {
+                    captures
+                } else {
+                    quiets
+                };
+                if wanted && targets & 1 << one != 0 {
+                    let mv = Move::new(&piece_id, from_square, Square::at_index(one), &6);
+                    ChessInstant::push_pawn_move(valid, mv);
+                }
+                let two = if one > from {
+                    one + 8
+                } else {
+                    one.wrapping_sub(8)
+                };
+                if quiets && from_square.row() == start_row && empty & targets & 1 << two != 0 {
+                    let mut mv = Move::new(&piece_id, from_square, Square::at_index(two), &6);
+                    mv.double_push = true;
+                    valid.push(mv);
+                }
+            }
+            // attacking movement
+            let attacks = bitboard::PAWN_ATTACKS[side][from];
+            if !captures {
+                continue;
+            }
+            for to in Squares(attacks & enemy & targets) {
+                let to = Square::at_index(to);
+                ChessInstant::push_pawn_move(
+                    valid,
+                    Move::new(&piece_id, from_square, to, &self.id_at(to)),
+                );
+            }
+            // en passant, left to is_legal when evading as the pawn taken is not on the target square
+            if attacks & passant != 0 {
+                let to = passant.trailing_zeros() as usize;
+                let mut mv = Move::new(&piece_id, from_square, Square::at_index(to), &6);
+                mv.captured = Some(Piece::Pawn);
+                mv.en_passant = true;
+                valid.push(mv);
+            }
         }
-        clone.prv_move = (
-            ChessInstant::encode_index(from_row, from_col),
-            ChessInstant::encode_index(to_row, to_col),
-        );
-        valid.push(clone);
     }
 
-    /// moves piece at from to to then checks if king is in check if not adds new game indtance to valid
-    fn make_move_standard(
-        &self,
-        mm: &MoveMap,
-        valid: &mut Vec<ChessInstant>,
-        piece_id: &u32,
-        from_row: &usize,
-        from_col: &u32,
-        to_row: &usize,
-        to_col: &u32,
-    ) {
-        let mut clone = *self;
-        clone.board_set(from_row, from_col, 6); // set from to blank value
-        clone.board_set(to_row, to_col, *piece_id); // set to to piece id
-        if clone.in_check(mm, piece_id) {
-            return; // not valid if in check
+    /// adds the pawn move, or all four promotions if the pawn reaches the end row
+    fn push_pawn_move(valid: &mut Vec<Move>, mv: Move) {
+        if mv.to.row() == 0 || mv.to.row() == 7 {
+            // if on end row promote that pawn!
+            for promotion in [Piece::Rook, Piece::Knight, Piece::Bishop, Piece::Queen] {
+                let mut promoted = mv;
+                promoted.promotion = Some(promotion);
+                valid.push(promoted);
+            }
+        } else {
+            valid.push(mv);
         }
-        clone.plie += 1;
-        clone.prv_move = (
-            ChessInstant::encode_index(from_row, from_col),
-            ChessInstant::encode_index(to_row, to_col),
-        );
-        valid.push(clone);
     }
 
     /// resets the en passant value for the current player
@@ -711,25 +807,39 @@ impl ChessInstant {
         }
     }
 
-    /// encodes a row and column into a u8 index
-    fn encode_index(row: &usize, col: &u32) -> u8 {
-        (row << 3) as u8 + *col as u8 // row * 8 + column
-    }
-
-    /// gives back the (row, column) for a given index
-    fn decode_index(index: &u8) -> (usize, u32) {
-        ((index >> 3) as usize, (index % 8) as u32)
-    }
-
     /// get the value at given row and column as a u32 btween 0-15,
     /// !!!unchecked bounds will panic if row or col is greater than 7!!!
+    #[deprecated(note = "use id_at with a Square")]
     pub fn board_get(&self, row: &usize, col: &u32) -> u32 {
+        self.id_at(Square::from_row_col(*row, *col).expect("row and col must be less than 8"))
+    }
+
+    /// the piece id on the square as a u32 between 0-15, 6 when the square is empty
+    pub fn id_at(&self, square: Square) -> u32 {
         // shift over the column to currect 8 bits, then exclude with the & the extra bits
-        self.board[*row] >> (col << 2) & 0x0000000f
+        self.board[square.row()] >> (square.col() << 2) & 0x0000000f
+    }
+
+    /// the same as board_get but gives back an error instead of panicking when row or col is greater than 7
+    pub fn try_board_get(&self, row: &usize, col: &u32) -> Result<u32, ChessError> {
+        Ok(self.id_at(Square::try_from((*row, *col))?))
+    }
+
+    /// the same as player_piece but gives back an error instead of panicking when row or col is greater than 7
+    pub fn try_player_piece(
+        &self,
+        row: &usize,
+        col: &u32,
+    ) -> Result<Option<(Player, Piece)>, ChessError> {
+        Ok(self.piece_at(Square::try_from((*row, *col))?))
     }
 
     /// sets the value at given row and column val should be between 0-15
-    fn board_set(&mut self, row: &usize, col: &u32, val: u32) {
+    pub(crate) fn board_set(&mut self, row: &usize, col: &u32, val: u32) {
+        let old = self.id_at(Square::at(*row, *col));
+        let square = Square::at(*row, *col).index();
+        self.bits.set(square, old, val);
+        self.hash ^= zobrist::piece_key(old, square) ^ zobrist::piece_key(val, square);
         // filter is used to clear whatever was in the location already
         let filter = u32::MAX ^ (0x0000000f << (col << 2));
         // (set the valid 4 bits to 0) + (the new value being set)
@@ -742,11 +852,16 @@ impl ChessInstant {
             board: [0; 8],
             prv_move: (0, 0),
             plie: 1,
+            halfmove: 0,
             p1_king: 60,
             p2_king: 4,
             p1_passant: 64,
             p2_passant: 64,
             valid_castles: (true, true, true, true),
+            castle_cols: standard_castle_cols(),
+            chess960: false,
+            bits: Bitboards::default(),
+            hash: 0,
         };
         // set all squares to none val
         for row in 0..8 {
@@ -779,6 +894,7 @@ impl ChessInstant {
         c.board_set(&7, &5, 2);
         c.board_set(&7, &6, 1);
         c.board_set(&7, &7, 0);
+        c.hash = c.compute_hash();
 
         c
     }
@@ -790,7 +906,7 @@ impl ChessInstant {
         println!("Plie: {}, {}", self.plie, self.player().as_str());
         for row in 0..8 {
             for col in 0..8 {
-                let str = match self.board_get(&row, &col) {
+                let str = match self.id_at(Square::at(row, col)) {
                     0 => "1RO",
                     1 => "1KN",
                     2 => "1BI",
@@ -813,6 +929,159 @@ impl ChessInstant {
     }
 }
 
+impl ChessInstant {
+    /// reads a chess instant from json, rejecting any position validate does not accept
+    pub fn from_json(json: &str) -> Result<ChessInstant, ChessError> {
+        let ci: ChessInstant = serde_json::from_str(json)?;
+        ci.validate()?;
+        Ok(ci)
+    }
+
+    /// checks the position could come up in a game, giving back the first reason it could not
+    pub fn validate(&self) -> Result<(), ChessError> {
+        for row in 0..8 {
+            for col in 0..8 {
+                let value = self.id_at(Square::at(row, col));
+                if value > 12 {
+                    return Err(ChessError::InvalidSquare { row, col, value });
+                }
+            }
+        }
+        for (player, index) in [(Player::P1, self.p1_king), (Player::P2, self.p2_king)] {
+            let kings = self.bits.pieces(Piece::King.to_id(&player));
+            if kings.count_ones() != 1 {
+                return Err(ChessError::KingCount(player, kings.count_ones() as usize));
+            }
+            if index > 63 || kings & (1 << index) == 0 {
+                return Err(ChessError::InvalidKingIndex(player, index));
+            }
+        }
+        // pawns promote on the last rank and can never move back to their own first rank
+        let back_ranks = 0xff | 0xff << 56;
+        let pawns = self.bits.pieces(Piece::Pawn.to_id(&Player::P1))
+            | self.bits.pieces(Piece::Pawn.to_id(&Player::P2));
+        if let Some(square) = Squares(pawns & back_ranks).next() {
+            let (row, col) = bitboard::row_col(square);
+            return Err(ChessError::PawnOnBackRank { row, col });
+        }
+        for (player, index) in [(Player::P1, self.p1_passant), (Player::P2, self.p2_passant)] {
+            if index > 64 {
+                return Err(ChessError::InvalidPassant(player, index));
+            }
+        }
+        // only the passant of the player who just moved can be taken, it has to be the square
+        // skipped by a pawn of theirs that could have just moved two squares
+        let (passant, row, pawn_row, mover) = match self.player() {
+            Player::P1 => (self.p2_passant, 2, 3, Player::P2),
+            Player::P2 => (self.p1_passant, 5, 4, Player::P1),
+        };
+        if let Some(square) = Square::from_index(passant) {
+            let pawn = self.id_at(Square::at(pawn_row, square.col()));
+            if square.row() != row || self.id_at(square) != 6 || pawn != Piece::Pawn.to_id(&mover) {
+                return Err(ChessError::InvalidEnPassantSquare(square));
+            }
+        }
+        // each castle needs the king and its rook on the back rank, outside of chess960
+        // the king has to be on the e file with the rooks in the corners
+        let (wq, wk, bq, bk) = self.valid_castles;
+        for (index, allowed) in [wq, wk, bq, bk].into_iter().enumerate() {
+            if !allowed {
+                continue;
+            }
+            let (player, row, king) = match index {
+                0 | 1 => (Player::P1, 7, self.p1_king),
+                _ => (Player::P2, 0, self.p2_king),
+            };
+            let king_side = index % 2 == 1;
+            let rook_col = self.castle_cols[index];
+            let king_col = king as u32 % 8;
+            let standard = king_col == 4 && rook_col == if king_side { 7 } else { 0 };
+            if king as usize / 8 != row
+                || rook_col > 7
+                || king_side != (rook_col > king_col)
+                || self.id_at(Square::at(row, rook_col)) != Piece::Rook.to_id(&player)
+                || !self.chess960 && !standard
+            {
+                return Err(ChessError::InvalidCastle { player, king_side });
+            }
+        }
+        let (from, to) = self.prv_move;
+        if from > 63 || to > 63 {
+            return Err(ChessError::InvalidPreviousMove(from, to));
+        }
+        // the last move can not have left the player who made it in check
+        let waiting = self.player().swap();
+        let king = match waiting {
+            Player::P1 => self.p1_king,
+            Player::P2 => self.p2_king,
+        };
+        if self.square_attacked(king as usize, &self.player()) {
+            return Err(ChessError::OpponentInCheck(waiting));
+        }
+        Ok(())
+    }
+}
+
+/// deserializes a chess instant and validates it, for use with #[serde(deserialize_with)]
+/// where the default Deserialize would accept any position
+pub fn deserialize_validated<'de, D>(deserializer: D) -> Result<ChessInstant, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let ci = ChessInstant::deserialize(deserializer)?;
+    ci.validate().map_err(serde::de::Error::custom)?;
+    Ok(ci)
+}
+
+impl Hash for ChessInstant {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl From<ChessInstantData> for ChessInstant {
+    fn from(data: ChessInstantData) -> ChessInstant {
+        let mut ci = ChessInstant {
+            board: data.board,
+            prv_move: data.prv_move,
+            plie: data.plie,
+            halfmove: data.halfmove,
+            p1_king: data.p1_king,
+            p2_king: data.p2_king,
+            p1_passant: data.p1_passant,
+            p2_passant: data.p2_passant,
+            valid_castles: data.valid_castles,
+            castle_cols: data.castle_cols,
+            chess960: data.chess960,
+            bits: Bitboards::default(),
+            hash: 0,
+        };
+        for square in Square::all() {
+            ci.bits.set(square.index(), 6, ci.id_at(square));
+        }
+        ci.hash = ci.compute_hash();
+        ci
+    }
+}
+
+impl From<ChessInstant> for ChessInstantData {
+    fn from(ci: ChessInstant) -> ChessInstantData {
+        ChessInstantData {
+            board: ci.board,
+            prv_move: ci.prv_move,
+            plie: ci.plie,
+            p1_king: ci.p1_king,
+            p2_king: ci.p2_king,
+            p1_passant: ci.p1_passant,
+            p2_passant: ci.p2_passant,
+            valid_castles: ci.valid_castles,
+            halfmove: ci.halfmove,
+            castle_cols: ci.castle_cols,
+            chess960: ci.chess960,
+        }
+    }
+}
+
 impl Player {
     fn as_str(&self) -> &'static str {
         match self {
@@ -829,7 +1098,43 @@ impl Player {
     }
 }
 
+impl Move {
+    /// a plain move of the piece id from one location to another, found is the id at the to location
+    fn new(piece_id: &u32, from: Square, to: Square, found: &u32) -> Move {
+        Move {
+            from,
+            to,
+            piece: Piece::from_id(piece_id),
+            captured: if *found == 6 {
+                None
+            } else {
+                Some(Piece::from_id(found))
+            },
+            promotion: None,
+            castle: false,
+            en_passant: false,
+            double_push: false,
+        }
+    }
+}
+
 impl Piece {
+    /// gives back the piece id used on the board for the player
+    pub fn to_id(&self, player: &Player) -> u32 {
+        let id = match self {
+            Piece::Rook => 0,
+            Piece::Knight => 1,
+            Piece::Bishop => 2,
+            Piece::Queen => 3,
+            Piece::King => 4,
+            Piece::Pawn => 5,
+        };
+        match player {
+            Player::P1 => id,
+            Player::P2 => id + 7,
+        }
+    }
+
     pub fn from_id(id: &u32) -> Piece {
         match id {
             0 | 7 => Piece::Rook,
@@ -841,3 +1146,11 @@ impl Piece {
         }
     }
 }
+
+/// the squares on the row from one column to another, both included
+fn rank_span(row: usize, a: u32, b: u32) -> u64 {
+    let (low, high) = (a.min(b), a.max(b));
+    // high - low + 1 bits starting at low
+    let span = (1u64 << (high - low + 1)) - 1;
+    span << (row * 8 + low as usize)
+}
@@ -0,0 +1,78 @@
+// this module sets up the 960 starting positions of Fischer Random chess
+
+use crate::chess::{ChessInstant, Piece, Player};
+use crate::error::ChessError;
+use crate::square::Square;
+
+// the columns of the two knights among the five columns left after the bishops and queen are placed
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl ChessInstant {
+    /// creates a chess960 game from its standard start number between 0 and 959,
+    /// 518 is the standard chess starting position
+    pub fn new_chess960(n: u16) -> Result<ChessInstant, ChessError> {
+        let pieces = chess960_back_rank(n)?;
+        let mut ci = ChessInstant::new();
+        let mut rooks = Vec::new();
+        for (col, piece) in pieces.iter().enumerate() {
+            let col = col as u32;
+            ci.board_set(&7, &col, piece.to_id(&Player::P1));
+            ci.board_set(&0, &col, piece.to_id(&Player::P2));
+            match piece {
+                Piece::King => {
+                    ci.p1_king = Square::at(7, col).into();
+                    ci.p2_king = Square::at(0, col).into();
+                }
+                Piece::Rook => rooks.push(col),
+                _ => {}
+            }
+        }
+        // the rook left of the king castles queen side and the one right of it king side
+        ci.castle_cols = [rooks[0], rooks[1], rooks[0], rooks[1]];
+        ci.chess960 = true;
+        ci.hash = ci.compute_hash();
+        Ok(ci)
+    }
+}
+
+/// the pieces of the back rank from column 0 for the start number, using the numbering
+/// where the bishops, queen and knights are placed in turn by dividing the number down
+fn chess960_back_rank(n: u16) -> Result<[Piece; 8], ChessError> {
+    if n >= 960 {
+        return Err(ChessError::Chess960Number(n));
+    }
+    let mut rank: [Option<Piece>; 8] = [None; 8];
+    let n = n as usize;
+    // the light square bishop on an odd column and the dark square bishop on an even one
+    rank[(n % 4) * 2 + 1] = Some(Piece::Bishop);
+    rank[(n / 4 % 4) * 2] = Some(Piece::Bishop);
+    let n = n / 16;
+    let empty = |rank: &[Option<Piece>; 8]| -> Vec<usize> {
+        (0..8).filter(|col| rank[*col].is_none()).collect()
+    };
+    let queen = empty(&rank)[n % 6];
+    rank[queen] = Some(Piece::Queen);
+    let (a, b) = KNIGHTS[n / 6];
+    let free = empty(&rank);
+    rank[free[a]] = Some(Piece::Knight);
+    rank[free[b]] = Some(Piece::Knight);
+    // the king goes between the two rooks on the last three columns
+    for (col, piece) in empty(&rank)
+        .into_iter()
+        .zip([Piece::Rook, Piece::King, Piece::Rook])
+    {
+        rank[col] = Some(piece);
+    }
+    Ok(rank.map(|piece| piece.unwrap()))
+}
@@ -0,0 +1,158 @@
+// this module holds the error returned by the fallible entry points of the crate
+
+use std::fmt;
+
+use crate::chess::Player;
+use crate::fen::FenError;
+use crate::pgn::PgnError;
+use crate::san::SanError;
+use crate::square::Square;
+use crate::uci::UciError;
+
+/// the reason a request to the crate could not be carried out
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ChessError {
+    /// the json could not be read as a chess instant
+    Json(String),
+    /// the fen could not be read as a chess instant
+    Fen(FenError),
+    /// the san could not be resolved to a legal move
+    San(SanError),
+    /// the pgn could not be read or replayed
+    Pgn(PgnError),
+    /// the uci string could not be resolved to a legal move
+    Uci(UciError),
+    /// the row or column is greater than 7
+    OutOfBounds { row: usize, col: u32 },
+    /// the index is past the last square, file or rank
+    IndexOutOfBounds(u8),
+    /// the square holds a value that is not a piece id or 6 for empty
+    InvalidSquare { row: usize, col: u32, value: u32 },
+    /// the player does not have exactly one king
+    KingCount(Player, usize),
+    /// the index kept for the player's king is not a square holding that king
+    InvalidKingIndex(Player, u8),
+    /// the en passant index kept for the player is not a square or 64 for none
+    InvalidPassant(Player, u8),
+    /// a pawn is on the first or last rank
+    PawnOnBackRank { row: usize, col: u32 },
+    /// the previous move holds an index that is not a square
+    InvalidPreviousMove(u8, u8),
+    /// the player who is not to move is in check
+    OpponentInCheck(Player),
+    /// the chess960 start number is not between 0 and 959
+    Chess960Number(u16),
+    /// the player is given a castle without their king and that rook on their starting squares
+    InvalidCastle { player: Player, king_side: bool },
+    /// the en passant square is not behind a pawn that could have just moved two squares
+    InvalidEnPassantSquare(Square),
+    /// a game does not have exactly one more instant than moves
+    GameLength { instants: usize, moves: usize },
+    /// the move at the index in a game is not legal or does not lead to the next instant
+    GameMove(usize),
+}
+
+impl fmt::Display for ChessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChessError::Json(e) => write!(f, "invalid chess instant json: {e}"),
+            ChessError::Fen(e) => e.fmt(f),
+            ChessError::San(e) => e.fmt(f),
+            ChessError::Pgn(e) => e.fmt(f),
+            ChessError::Uci(e) => e.fmt(f),
+            ChessError::OutOfBounds { row, col } => {
+                write!(f, "row {row} column {col} is not on the board")
+            }
+            ChessError::IndexOutOfBounds(index) => write!(f, "index {index} is not on the board"),
+            ChessError::InvalidSquare { row, col, value } => {
+                write!(f, "row {row} column {col} holds {value}, not a piece")
+            }
+            ChessError::KingCount(player, n) => {
+                write!(f, "{player:?} has {n} kings, expected exactly 1")
+            }
+            ChessError::InvalidKingIndex(player, index) => {
+                write!(f, "{player:?} king index {index} does not hold their king")
+            }
+            ChessError::InvalidPassant(player, index) => {
+                write!(f, "{player:?} en passant index {index} is not a square")
+            }
+            ChessError::PawnOnBackRank { row, col } => {
+                write!(f, "row {row} column {col} holds a pawn on a back rank")
+            }
+            ChessError::InvalidPreviousMove(from, to) => {
+                write!(f, "previous move ({from}, {to}) is not between two squares")
+            }
+            ChessError::OpponentInCheck(player) => {
+                write!(f, "{player:?} is in check but it is not their move")
+            }
+            ChessError::Chess960Number(n) => {
+                write!(f, "{n} is not a chess960 start number, expected 0 to 959")
+            }
+            ChessError::InvalidCastle { player, king_side } => {
+                let side = if *king_side { "king" } else { "queen" };
+                write!(
+                    f,
+                    "{player:?} can not castle {side} side from this position"
+                )
+            }
+            ChessError::InvalidEnPassantSquare(square) => {
+                write!(
+                    f,
+                    "{square} is not behind a pawn that just moved two squares"
+                )
+            }
+            ChessError::GameLength { instants, moves } => {
+                write!(
+                    f,
+                    "game has {instants} instants for {moves} moves, expected one more"
+                )
+            }
+            ChessError::GameMove(index) => write!(
+                f,
+                "game move {index} is not legal or does not lead to the next instant"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChessError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChessError::Fen(e) => Some(e),
+            ChessError::San(e) => Some(e),
+            ChessError::Pgn(e) => Some(e),
+            ChessError::Uci(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<FenError> for ChessError {
+    fn from(e: FenError) -> ChessError {
+        ChessError::Fen(e)
+    }
+}
+
+impl From<SanError> for ChessError {
+    fn from(e: SanError) -> ChessError {
+        ChessError::San(e)
+    }
+}
+
+impl From<PgnError> for ChessError {
+    fn from(e: PgnError) -> ChessError {
+        ChessError::Pgn(e)
+    }
+}
+
+impl From<UciError> for ChessError {
+    fn from(e: UciError) -> ChessError {
+        ChessError::Uci(e)
+    }
+}
+
+impl From<serde_json::Error> for ChessError {
+    fn from(e: serde_json::Error) -> ChessError {
+        ChessError::Json(e.to_string())
+    }
+}
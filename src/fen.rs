@@ -0,0 +1,350 @@
+// this module reads and writes chess instants as Forsyth-Edwards Notation
+
+use std::fmt;
+
+use crate::bitboard;
+use crate::chess::{ChessInstant, Piece, Player};
+use crate::error::ChessError;
+use crate::square::Square;
+
+/// the fen for the standard starting position
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// the reason a fen string could not be read
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FenError {
+    /// the named field is missing from the string
+    MissingField(&'static str),
+    /// there is more than the six fen fields
+    TooManyFields,
+    /// the board does not have 8 ranks separated by '/'
+    RankCount(usize),
+    /// the rank (8 at the top) does not describe exactly 8 squares
+    RankLength(usize),
+    /// a character on the board that is not a piece or a number of empty squares
+    InvalidPiece(char),
+    /// the player does not have exactly one king
+    KingCount(Player, usize),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmove(String),
+    InvalidFullmove(String),
+    /// the fields are readable but the position could not come up in a game
+    InvalidPosition(Box<ChessError>),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "fen is missing the {field} field"),
+            FenError::TooManyFields => write!(f, "fen has more than six fields"),
+            FenError::RankCount(n) => write!(f, "fen board has {n} ranks, expected 8"),
+            FenError::RankLength(rank) => {
+                write!(f, "fen rank {rank} does not describe exactly 8 squares")
+            }
+            FenError::InvalidPiece(c) => write!(f, "'{c}' is not a valid fen piece"),
+            FenError::KingCount(player, n) => {
+                write!(f, "{player:?} has {n} kings, expected exactly 1")
+            }
+            FenError::InvalidSideToMove(s) => write!(f, "'{s}' is not a valid side to move"),
+            FenError::InvalidCastling(s) => write!(f, "'{s}' is not a valid castling field"),
+            FenError::InvalidEnPassant(s) => write!(f, "'{s}' is not a valid en passant square"),
+            FenError::InvalidHalfmove(s) => write!(f, "'{s}' is not a valid halfmove clock"),
+            FenError::InvalidFullmove(s) => write!(f, "'{s}' is not a valid fullmove number"),
+            FenError::InvalidPosition(e) => write!(f, "fen position is not possible: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FenError::InvalidPosition(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl ChessInstant {
+    /// creates a chess instant from a fen string, the halfmove and fullmove fields are optional,
+    /// positions that do not pass validate are not accepted
+    pub fn from_fen(fen: &str) -> Result<ChessInstant, FenError> {
+        let mut fields = fen.split_whitespace();
+        let board = fields.next().ok_or(FenError::MissingField("board"))?;
+        let side = fields
+            .next()
+            .ok_or(FenError::MissingField("side to move"))?;
+        let castling = fields.next().ok_or(FenError::MissingField("castling"))?;
+        let passant = fields.next().ok_or(FenError::MissingField("en passant"))?;
+        let halfmove = fields.next().unwrap_or("0");
+        let fullmove = fields.next().unwrap_or("1");
+        if fields.next().is_some() {
+            return Err(FenError::TooManyFields);
+        }
+
+        let mut ci = ChessInstant::new();
+        ci.prv_move = (0, 0);
+
+        // board, the first rank given is row 0
+        let ranks: Vec<&str> = board.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+        let mut kings = (Vec::new(), Vec::new());
+        for (row, rank) in ranks.iter().enumerate() {
+            let mut col: u32 = 0;
+            for c in rank.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    if empty == 0 || empty > 8 {
+                        return Err(FenError::InvalidPiece(c));
+                    }
+                    for _ in 0..empty {
+                        if col < 8 {
+                            ci.board_set(&row, &col, 6);
+                        }
+                        col += 1;
+                    }
+                    continue;
+                }
+                let id = piece_id(c).ok_or(FenError::InvalidPiece(c))?;
+                if col < 8 {
+                    ci.board_set(&row, &col, id);
+                    if id == 4 {
+                        kings.0.push(Square::at(row, col).into());
+                    } else if id == 11 {
+                        kings.1.push(Square::at(row, col).into());
+                    }
+                }
+                col += 1;
+            }
+            if col != 8 {
+                return Err(FenError::RankLength(8 - row));
+            }
+        }
+        if kings.0.len() != 1 {
+            return Err(FenError::KingCount(Player::P1, kings.0.len()));
+        }
+        if kings.1.len() != 1 {
+            return Err(FenError::KingCount(Player::P2, kings.1.len()));
+        }
+        ci.p1_king = kings.0[0];
+        ci.p2_king = kings.1[0];
+
+        // side to move, white moves on odd plies
+        let white = match side {
+            "w" => true,
+            "b" => false,
+            _ => return Err(FenError::InvalidSideToMove(side.to_string())),
+        };
+
+        // castling rights in the order white queen side, white king side, black queen side, black king side,
+        // K and Q give the outermost rook on that side of the king and a file letter names the rook
+        // like in Shredder and X-FEN for chess960
+        ci.valid_castles = (false, false, false, false);
+        ci.castle_cols = [0, 7, 0, 7];
+        if castling != "-" {
+            let invalid = || FenError::InvalidCastling(castling.to_string());
+            for c in castling.chars() {
+                let player = if c.is_ascii_uppercase() {
+                    Player::P1
+                } else {
+                    Player::P2
+                };
+                let (row, king_col) = match player {
+                    Player::P1 => (7, ci.p1_king as u32 % 8),
+                    Player::P2 => (0, ci.p2_king as u32 % 8),
+                };
+                let rook = Piece::Rook.to_id(&player);
+                let is_rook = |col: &u32| ci.id_at(Square::at(row, *col)) == rook;
+                let (kingside, col) = match c.to_ascii_lowercase() {
+                    'k' => (
+                        true,
+                        (king_col + 1..8).rev().find(is_rook).ok_or_else(invalid)?,
+                    ),
+                    'q' => (false, (0..king_col).find(is_rook).ok_or_else(invalid)?),
+                    file @ 'a'..='h' => {
+                        let col = file as u32 - 'a' as u32;
+                        if col == king_col {
+                            return Err(invalid());
+                        }
+                        (col > king_col, col)
+                    }
+                    _ => return Err(invalid()),
+                };
+                let index = bitboard::side(&player) * 2 + kingside as usize;
+                let right = match index {
+                    0 => &mut ci.valid_castles.0,
+                    1 => &mut ci.valid_castles.1,
+                    2 => &mut ci.valid_castles.2,
+                    _ => &mut ci.valid_castles.3,
+                };
+                if *right {
+                    return Err(invalid());
+                }
+                *right = true;
+                ci.castle_cols[index] = col;
+            }
+        }
+        // any castle that is not the king on the e file with a rook in the corner follows the chess960 rules
+        let (wq, wk, bq, bk) = ci.valid_castles;
+        let kings_moved = [(wq || wk, ci.p1_king), (bq || bk, ci.p2_king)]
+            .iter()
+            .any(|(castles, king)| *castles && king % 8 != 4);
+        ci.chess960 = kings_moved || ci.castle_cols != [0, 7, 0, 7];
+
+        // en passant is stored on the player who made the double move
+        ci.p1_passant = 64;
+        ci.p2_passant = 64;
+        if passant != "-" {
+            let invalid = || FenError::InvalidEnPassant(passant.to_string());
+            let (row, col) = parse_square(passant).ok_or_else(invalid)?;
+            match (row, white) {
+                (5, false) => ci.p1_passant = Square::at(row, col).into(),
+                (2, true) => ci.p2_passant = Square::at(row, col).into(),
+                _ => return Err(invalid()),
+            }
+        }
+
+        ci.halfmove = halfmove
+            .parse::<u16>()
+            .map_err(|_| FenError::InvalidHalfmove(halfmove.to_string()))?;
+        let fullmove = match fullmove.parse::<u16>() {
+            Ok(n) if n > 0 && n < u16::MAX / 2 => n,
+            _ => return Err(FenError::InvalidFullmove(fullmove.to_string())),
+        };
+        ci.plie = if white {
+            fullmove * 2 - 1
+        } else {
+            fullmove * 2
+        };
+        ci.hash = ci.compute_hash();
+
+        ci.validate()
+            .map_err(|e| FenError::InvalidPosition(Box::new(e)))?;
+        Ok(ci)
+    }
+
+    /// gives back the fen string for the chess instant
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::with_capacity(90);
+
+        for row in 0..8 {
+            let mut empty = 0;
+            for col in 0..8 {
+                let id = self.id_at(Square::at(row, col));
+                if id == 6 {
+                    empty += 1;
+                    continue;
+                }
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                fen.push(piece_char(&id));
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if row < 7 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.player() {
+            Player::P1 => " w ",
+            Player::P2 => " b ",
+        });
+
+        let (wq, wk, bq, bk) = self.valid_castles;
+        if !(wq || wk || bq || bk) {
+            fen.push('-');
+        }
+        for (valid, index, c) in [(wk, 1, 'K'), (wq, 0, 'Q'), (bk, 3, 'k'), (bq, 2, 'q')] {
+            if valid {
+                fen.push(self.castle_char(index, c));
+            }
+        }
+
+        // only the passant of the player that just moved can be taken
+        let passant = match self.player() {
+            Player::P1 => self.p2_passant,
+            Player::P2 => self.p1_passant,
+        };
+        fen.push(' ');
+        match Square::from_index(passant) {
+            Some(square) => fen.push_str(&square.to_string()),
+            None => fen.push('-'),
+        }
+
+        fen.push_str(&format!(" {} {}", self.halfmove, self.plie.div_ceil(2)));
+        fen
+    }
+}
+
+impl ChessInstant {
+    /// the letter for the castle at the index of valid_castles, K or Q unless another rook
+    /// is further out on that side of the king in which case the file of the rook is given
+    fn castle_char(&self, index: usize, c: char) -> char {
+        let (row, player) = match index {
+            0 | 1 => (7, Player::P1),
+            _ => (0, Player::P2),
+        };
+        let col = self.castle_cols[index];
+        let rook = Piece::Rook.to_id(&player);
+        // queen side castles are at even indexes
+        let mut outer = match index {
+            0 | 2 => 0..col,
+            _ => col + 1..8,
+        };
+        if !outer.any(|c| self.id_at(Square::at(row, c)) == rook) {
+            return c;
+        }
+        let file = (b'a' + col as u8) as char;
+        match player {
+            Player::P1 => file.to_ascii_uppercase(),
+            Player::P2 => file,
+        }
+    }
+}
+
+/// the board id for a fen piece character
+fn piece_id(c: char) -> Option<u32> {
+    let piece = match c.to_ascii_lowercase() {
+        'r' => Piece::Rook,
+        'n' => Piece::Knight,
+        'b' => Piece::Bishop,
+        'q' => Piece::Queen,
+        'k' => Piece::King,
+        'p' => Piece::Pawn,
+        _ => return None,
+    };
+    let player = if c.is_ascii_uppercase() {
+        Player::P1
+    } else {
+        Player::P2
+    };
+    Some(piece.to_id(&player))
+}
+
+/// the fen piece character for a board id
+fn piece_char(id: &u32) -> char {
+    let c = match Piece::from_id(id) {
+        Piece::Rook => 'r',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+        Piece::Pawn => 'p',
+    };
+    if *id < 6 {
+        c.to_ascii_uppercase()
+    } else {
+        c
+    }
+}
+
+/// reads a square like "e3" into a row and column
+pub(crate) fn parse_square(square: &str) -> Option<(usize, u32)> {
+    square.parse::<Square>().ok().map(Into::into)
+}
@@ -0,0 +1,139 @@
+// this module keeps the history of a game so repeated positions can be found
+
+use crate::chess::{ChessInstant, Move};
+use crate::error::ChessError;
+use crate::movemap::MoveMap;
+use serde::{Deserialize, Serialize};
+
+/// a game from its first position with every move played and the instant each move led to
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "GameData")]
+pub struct Game {
+    instants: Vec<ChessInstant>,
+    moves: Vec<Move>,
+}
+
+/// the serialized layout of a game, checked before it is turned into a game
+#[derive(Deserialize)]
+#[serde(rename = "Game")]
+struct GameData {
+    instants: Vec<ChessInstant>,
+    moves: Vec<Move>,
+}
+
+impl Game {
+    /// creates a new game from the starting position
+    pub fn new() -> Game {
+        Game::from_instant(ChessInstant::new())
+    }
+
+    /// creates a game that starts from the given position
+    pub fn from_instant(start: ChessInstant) -> Game {
+        Game {
+            instants: vec![start],
+            moves: Vec::new(),
+        }
+    }
+
+    /// the position the game is in now
+    pub fn current(&self) -> &ChessInstant {
+        self.instants.last().unwrap()
+    }
+
+    /// every position in the game from the first to the current one
+    pub fn instants(&self) -> &[ChessInstant] {
+        &self.instants
+    }
+
+    /// every move played in the game, moves()[i] goes from instants()[i] to instants()[i + 1]
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// plays the move from the current position, the move is not checked so it should come from legal_moves
+    pub fn play(&mut self, mv: Move) {
+        let next = self.current().apply(mv);
+        self.instants.push(next);
+        self.moves.push(mv);
+    }
+
+    /// takes back the last move, gives back none if there are no moves to take back
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        self.instants.pop();
+        Some(mv)
+    }
+
+    /// the zobrist keys of every position in the game, used by the searches to find repeats
+    pub fn history_keys(&self) -> Vec<u64> {
+        self.instants.iter().map(|ci| ci.hash_key()).collect()
+    }
+
+    /// the number of times the current position has come up in the game, counting itself
+    pub fn repetitions(&self) -> usize {
+        let current = self.current();
+        let key = current.hash_key();
+        let before = &self.instants[..self.instants.len() - 1];
+        let reversible = (current.halfmove_clock() as usize).min(before.len());
+        // only positions with the same player to move and no capture or pawn move since can be the same
+        1 + (2..=reversible)
+            .step_by(2)
+            .filter(|back| before[before.len() - back].hash_key() == key)
+            .count()
+    }
+
+    /// the current position has come up three times, a draw if a player claims it
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 3
+    }
+
+    /// the current position has come up five times, the game is drawn without a claim
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.repetitions() >= 5
+    }
+}
+
+impl TryFrom<GameData> for Game {
+    type Error = ChessError;
+
+    /// a game needs a start position and one instant after each move, every one of them valid
+    /// and each move legal and leading to the instant after it
+    fn try_from(data: GameData) -> Result<Game, ChessError> {
+        if data.instants.len() != data.moves.len() + 1 {
+            return Err(ChessError::GameLength {
+                instants: data.instants.len(),
+                moves: data.moves.len(),
+            });
+        }
+        for ci in &data.instants {
+            ci.validate()?;
+        }
+        let mm = MoveMap::new();
+        for (index, mv) in data.moves.iter().enumerate() {
+            let ci = &data.instants[index];
+            if !ci.legal_moves(&mm).contains(mv) || ci.apply(*mv) != data.instants[index + 1] {
+                return Err(ChessError::GameMove(index));
+            }
+        }
+        Ok(Game {
+            instants: data.instants,
+            moves: data.moves,
+        })
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+/// determines if the position came up before in the history, history holds the keys of
+/// every position before the instant in the order they were played
+pub(crate) fn is_repeat(ci: &ChessInstant, history: &[u64]) -> bool {
+    let key = ci.hash_key();
+    let reversible = (ci.halfmove_clock() as usize).min(history.len());
+    (2..=reversible)
+        .step_by(2)
+        .any(|back| history[history.len() - back] == key)
+}
@@ -2,6 +2,7 @@ pub mod heuristic_v1 {
     use crate::chess::{ChessInstant, Piece, Player};
     use crate::minmax::BotSettings;
     use crate::movemap::MoveMap;
+    use crate::square::Square;
     use std::ops::Range;
 
     pub fn parsed_score(ci: &ChessInstant, mm: &MoveMap) -> String {
@@ -28,10 +29,10 @@ pub mod heuristic_v1 {
     }
 
     /// gives back a suggested depth for the min max fn
-    pub fn dyn_depth(ci: &ChessInstant, mm: &MoveMap) -> i32 {
+    pub fn dyn_depth(ci: &ChessInstant, _mm: &MoveMap) -> i32 {
         let mut piece_val = 0;
-        for (row, col) in mm.locations() {
-            let piece = ci.board_get(row, col);
+        for square in Square::all() {
+            let piece = ci.id_at(square);
             if piece == 6 {
                 continue;
             }
@@ -78,11 +79,11 @@ pub mod heuristic_v1 {
     }
 
     /// material value of the board
-    fn material(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
+    fn material(ci: &ChessInstant, _mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
         let mut pieces_value = 0;
 
-        for (row, col) in mm.locations() {
-            let piece = ci.board_get(row, col);
+        for square in Square::all() {
+            let piece = ci.id_at(square);
             if piece == 6 {
                 continue;
             }
@@ -109,16 +110,16 @@ pub mod heuristic_v1 {
     }
 
     /// score for the players position pieces on the center being better
-    fn position(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
+    fn position(ci: &ChessInstant, _mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
         let mut position = 0;
-        for (row, col) in mm.locations() {
-            let piece = ci.board_get(row, col);
+        for square in Square::all() {
+            let piece = ci.id_at(square);
             if piece == 6 {
                 continue;
             }
             // r c will give value 0-3 depening on how far from the center the location is
-            let r = 3.5 - (*row as f64 - 3.5).abs();
-            let c = 3.5 - (*col as f64 - 3.5).abs();
+            let r = 3.5 - (square.row() as f64 - 3.5).abs();
+            let c = 3.5 - (square.col() as f64 - 3.5).abs();
             if f_range.contains(&piece) {
                 position += (r + c) as i32; // between 0-6 depending on how close to center
             }
@@ -128,14 +129,20 @@ pub mod heuristic_v1 {
     }
 
     /// gives a negetive score of the piece values that are under attack
-    fn attacked(ci: &ChessInstant, mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
+    fn attacked(ci: &ChessInstant, _mm: &MoveMap, bot: &BotSettings, f_range: &Range<u32>) -> i32 {
+        let opponent = if f_range.contains(&0) {
+            Player::P2
+        } else {
+            Player::P1
+        };
+        let attacks = ci.attacks_by(&opponent);
         let mut danger = 0;
-        for (row, col) in mm.locations() {
-            let piece = ci.board_get(row, col);
+        for square in Square::all() {
+            let piece = ci.id_at(square);
             if piece == 6 {
                 continue;
             }
-            if f_range.contains(&piece) && ci.threatened(mm, row, col, &piece) {
+            if f_range.contains(&piece) && attacks.contains(square) {
                 danger -= Piece::piece_value(&piece, bot);
             }
         }
@@ -156,28 +163,4 @@ pub mod heuristic_v1 {
             }
         }
     }
-
-    impl ChessInstant {
-        fn threatened(&self, mm: &MoveMap, row: &usize, col: &u32, id: &u32) -> bool {
-            if *id == 6 || *id > 12 {
-                return false;
-            }
-
-            let movements = mm.king_cover(&id, row, col);
-            'paths: for path in &movements.cover {
-                for (r, c, ids) in path {
-                    let f = self.board_get(r, c);
-                    if f == 6 {
-                        continue;
-                    }
-                    if !self.is_friendly(&id, &f) && ids.contains(&f) {
-                        return true;
-                    }
-                    continue 'paths;
-                }
-            }
-
-            false
-        }
-    }
 }
@@ -0,0 +1,176 @@
+// this module decides if a pseudo legal move leaves the king in check without making the move,
+// using the pieces giving check and the pieces pinned to the king
+
+use crate::attacks::SquareSet;
+use crate::bitboard::{self, Squares};
+use crate::chess::{ChessInstant, Move, Piece, Player};
+use crate::square::Square;
+
+/// a piece that can not leave the line between its king and an opponent slider
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Pin {
+    /// the square of the pinned piece
+    pub pinned: Square,
+    /// the square of the opponent slider pinning it
+    pub pinner: Square,
+    /// the squares the pinned piece can still move to, from next to the king up to and taking the pinner
+    pub line: SquareSet,
+}
+
+/// the pieces checking the player to move and the ones pinned to their king, found once for a
+/// position so each of its moves only needs a few bit tests
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Legality {
+    checkers: u64,
+    pinned: u64,
+}
+
+impl ChessInstant {
+    /// determines if the pseudo legal move from pseudo_legal_moves can be played without
+    /// leaving the player's own king in check
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.is_legal_with(mv, &self.legality())
+    }
+
+    /// the squares of the opponent pieces giving check to the current player's king
+    pub fn checkers(&self) -> SquareSet {
+        SquareSet::from(self.checker_bits(&self.player()))
+    }
+
+    /// the player's pieces pinned to their king, with the line each one can still move along
+    pub fn pinned_pieces(&self, player: &Player) -> Vec<Pin> {
+        let king = self.king_square(player);
+        let own = self.bits.player(player);
+        self.line_blockers(player, &player.swap())
+            .filter(|(blocker, _)| own & 1 << blocker != 0)
+            .map(|(pinned, pinner)| Pin {
+                pinned: Square::at_index(pinned),
+                pinner: Square::at_index(pinner),
+                line: SquareSet::from(
+                    (bitboard::between(king, pinner) | 1 << pinner) & !(1 << pinned),
+                ),
+            })
+            .collect()
+    }
+
+    /// the current player's pieces standing between one of their sliders and the opponent king,
+    /// moving one of them off the line gives a discovered check
+    pub fn discovered_check_candidates(&self) -> SquareSet {
+        let player = self.player();
+        let candidates = self
+            .line_blockers(&player.swap(), &player)
+            .filter(|(blocker, _)| self.bits.player(&player) & 1 << blocker != 0)
+            .fold(0, |set, (blocker, _)| set | 1 << blocker);
+        SquareSet::from(candidates)
+    }
+
+    /// keeps only the legal moves, the checkers and pins are found once for all of them
+    pub(crate) fn retain_legal(&self, moves: &mut Vec<Move>) {
+        let legality = self.legality();
+        moves.retain(|mv| self.is_legal_with(*mv, &legality));
+    }
+
+    /// the checkers and pins of the player to move for is_legal_with
+    pub(crate) fn legality(&self) -> Legality {
+        let player = self.player();
+        Legality {
+            checkers: self.checker_bits(&player),
+            pinned: self.pinned_bits(&player),
+        }
+    }
+
+    /// is_legal given the legality of the position the move is made from
+    pub(crate) fn is_legal_with(&self, mv: Move, legality: &Legality) -> bool {
+        let Legality { checkers, pinned } = *legality;
+        let player = self.player();
+        let king = self.king_square(&player);
+        let from = mv.from.index();
+        let to = mv.to.index();
+
+        // the rare moves that change more than two squares are checked by making them
+        if mv.castle || mv.en_passant {
+            let mut scratch = *self;
+            scratch.make_move(mv);
+            return !scratch.square_attacked(scratch.king_square(&player), &player.swap());
+        }
+        if mv.piece == Piece::King {
+            // the king can not hide behind itself from a slider
+            let occupied = self.bits.occupied() & !(1 << from);
+            return self.attackers_to(to, occupied) & self.bits.player(&player.swap()) == 0;
+        }
+
+        match checkers.count_ones() {
+            0 => {}
+            // the only answer to a single check is taking the checker or getting in the way
+            1 => {
+                let checker = checkers.trailing_zeros() as usize;
+                if (bitboard::between(king, checker) | checkers) & 1 << to == 0 {
+                    return false;
+                }
+            }
+            // only the king can move out of a double check
+            _ => return false,
+        }
+        // a pinned piece can only move along the line to its king
+        pinned & 1 << from == 0 || bitboard::line(king, from) & 1 << to != 0
+    }
+
+    /// the square index of the player's king
+    pub(crate) fn king_square(&self, player: &Player) -> usize {
+        match player {
+            Player::P1 => self.p1_king as usize,
+            Player::P2 => self.p2_king as usize,
+        }
+    }
+
+    /// every piece of either player attacking the square given the occupied squares
+    pub(crate) fn attackers_to(&self, square: usize, occupied: u64) -> u64 {
+        let bits = &self.bits;
+        let both = |piece: Piece| {
+            bits.pieces(piece.to_id(&Player::P1)) | bits.pieces(piece.to_id(&Player::P2))
+        };
+        let straight = both(Piece::Rook) | both(Piece::Queen);
+        let diagonal = both(Piece::Bishop) | both(Piece::Queen);
+        // a pawn attacks the square if the square would attack the pawn as the other player
+        bitboard::PAWN_ATTACKS[0][square] & bits.pieces(Piece::Pawn.to_id(&Player::P2))
+            | bitboard::PAWN_ATTACKS[1][square] & bits.pieces(Piece::Pawn.to_id(&Player::P1))
+            | bitboard::KNIGHT_ATTACKS[square] & both(Piece::Knight)
+            | bitboard::KING_ATTACKS[square] & both(Piece::King)
+            | bitboard::rook_attacks(square, occupied) & straight
+            | bitboard::bishop_attacks(square, occupied) & diagonal
+    }
+
+    /// the opponent pieces giving check to the player's king
+    pub(crate) fn checker_bits(&self, player: &Player) -> u64 {
+        let king = self.king_square(player);
+        self.attackers_to(king, self.bits.occupied()) & self.bits.player(&player.swap())
+    }
+
+    /// the player's pieces that are the only piece between their king and an opponent slider
+    pub(crate) fn pinned_bits(&self, player: &Player) -> u64 {
+        let own = self.bits.player(player);
+        self.line_blockers(player, &player.swap())
+            .filter(|(blocker, _)| own & 1 << blocker != 0)
+            .fold(0, |set, (blocker, _)| set | 1 << blocker)
+    }
+
+    /// (blocker, slider) for every slider of the attacker with exactly one piece of either
+    /// player between it and the king's player's king
+    fn line_blockers(
+        &self,
+        king_player: &Player,
+        attacker: &Player,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let king = self.king_square(king_player);
+        let occupied = self.bits.occupied();
+        let pieces = |piece: Piece| self.bits.pieces(piece.to_id(attacker));
+        let queens = pieces(Piece::Queen);
+        // sliders that would attack the king if nothing stood in the way
+        let snipers = bitboard::rook_attacks(king, 0) & (pieces(Piece::Rook) | queens)
+            | bitboard::bishop_attacks(king, 0) & (pieces(Piece::Bishop) | queens);
+        Squares(snipers).filter_map(move |sniper| {
+            let blockers = bitboard::between(king, sniper) & occupied;
+            (blockers.count_ones() == 1).then(|| (blockers.trailing_zeros() as usize, sniper))
+        })
+    }
+}
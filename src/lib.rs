@@ -1,12 +1,30 @@
+pub mod attacks;
+mod bitboard;
 pub mod bot_0;
 pub mod bot_1;
+pub mod builder;
 pub mod chess;
+pub mod chess960;
+pub mod error;
+pub mod fen;
+pub mod game;
 pub mod heuristic;
+pub mod legality;
 pub mod minmax;
 pub mod movemap;
+pub mod perft;
+pub mod pgn;
+pub mod san;
+mod see;
+pub mod square;
+pub mod status;
+pub mod uci;
+mod zobrist;
 
-use crate::chess::{ChessInstant, Piece, Player};
+use crate::chess::{ChessInstant, Move, Piece, Player};
 
+use error::ChessError;
+use fen::FenError;
 use movemap::MoveMap;
 
 pub fn new_chess_instant() -> ChessInstant {
@@ -21,6 +39,10 @@ pub fn valid_games(chess_instant: &mut ChessInstant, move_map: &MoveMap) -> Vec<
     chess_instant.valid_games(move_map)
 }
 
+pub fn legal_moves(chess_instant: &ChessInstant, move_map: &MoveMap) -> Vec<Move> {
+    chess_instant.legal_moves(move_map)
+}
+
 pub fn decode_board(chess_instant: &ChessInstant) -> Vec<Vec<Option<(Player, Piece)>>> {
     chess_instant.decode_board()
 }
@@ -29,6 +51,14 @@ pub fn game_from_json_unchecked(game_json: &String) -> ChessInstant {
     serde_json::from_str(&game_json).unwrap()
 }
 
+pub fn game_from_json(game_json: &str) -> Result<ChessInstant, ChessError> {
+    ChessInstant::from_json(game_json)
+}
+
+pub fn game_from_fen(fen: &str) -> Result<ChessInstant, FenError> {
+    ChessInstant::from_fen(fen)
+}
+
 pub fn heuristic_v1_moves(chess_instant: &ChessInstant) -> Vec<(ChessInstant, i32)> {
     let mm = MoveMap::new();
     let depth = heuristic::heuristic_v1::dyn_depth(chess_instant, &mm);
@@ -42,7 +72,26 @@ pub fn heuristic_v1_moves(chess_instant: &ChessInstant) -> Vec<(ChessInstant, i3
 mod tests {
     // run to print statements: cargo test -- --nocapture
     //use crate::chess::ChessInstant;
-    use crate::{decode_board, new_chess_instant, new_movement_map};
+    use crate::bot_1;
+    use crate::builder::PositionBuilder;
+    use crate::chess::{ChessInstant, Move, Piece, Player};
+    use crate::error::ChessError;
+    use crate::fen::{FenError, STARTING_FEN};
+    use crate::game::Game;
+    use crate::heuristic;
+    use crate::minmax::{minimax, minimax_game, BotSettings};
+    use crate::movemap::MoveMap;
+    use crate::perft;
+    use crate::pgn::{self, read_pgn, write_pgn, PgnError};
+    use crate::san::SanError;
+    use crate::square::{File, Rank, Square};
+    use crate::status::GameStatus;
+    use crate::uci::UciError;
+    use crate::{
+        decode_board, game_from_fen, game_from_json, game_from_json_unchecked, legal_moves,
+        new_chess_instant, new_movement_map,
+    };
+    use std::collections::HashSet;
 
     #[test]
     fn decode() {
@@ -53,50 +102,1361 @@ mod tests {
     }
 
     #[test]
-    fn six_plie() {
-        let time = std::time::Instant::now();
+    fn moves_match_valid_games() {
         let game = new_chess_instant();
         let mm = new_movement_map();
-        let mut games = Vec::with_capacity(125_000_000);
-        games.push(game);
-        for i in 1..=6 {
-            let mut checkmates = 0;
-            let mut found = Vec::with_capacity(i * 21_000_000);
-            for g in &mut games {
-                let vg = g.valid_games(&mm);
-                if vg.len() == 0 {
-                    checkmates += 1;
-                } else {
-                    found.extend(vg);
+        let moves = legal_moves(&game, &mm);
+        assert_eq!(20, moves.len());
+        let applied: Vec<_> = moves.iter().map(|mv| game.apply(*mv)).collect();
+        assert_eq!(game.valid_games(&mm), applied);
+
+        let double = moves
+            .iter()
+            .find(|mv| mv.from == Square::at(6, 4) && mv.to == Square::at(4, 4))
+            .unwrap();
+        assert_eq!(Piece::Pawn, double.piece);
+        assert!(double.double_push);
+        assert_eq!(None, double.captured);
+    }
+
+    #[test]
+    fn is_legal() {
+        let mm = new_movement_map();
+        // checks is_legal against making every pseudo legal move and looking for check
+        fn walk(ci: &mut ChessInstant, mm: &MoveMap, depth: u32) {
+            let king_id = ci.king_id();
+            for mv in ci.pseudo_legal_moves() {
+                let undo = ci.make_move(mv);
+                let legal = !ci.in_check(mm, &king_id);
+                if legal && depth > 1 {
+                    walk(ci, mm, depth - 1);
                 }
+                ci.unmake_move(undo);
+                assert_eq!(legal, ci.is_legal(mv), "{} {mv:?}", ci.to_fen());
             }
-            games = found;
-            let shannon = match i {
-                1 => 20,
-                2 => 400,
-                3 => 8_902,
-                4 => 197_281,
-                5 => 4_865_609,
-                6 => 119_060_324,
-                _ => 0,
-            };
-            assert_eq!(games.len(), shannon);
-
-            let check = match i {
-                1 => 0,
-                2 => 0,
-                3 => 0,
-                4 => 0,
-                5 => 8,
-                6 => 347,
-                _ => 0,
+        }
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            // a check and a pin on the same line as the king
+            "4k3/8/8/8/1b6/8/3N4/r3K3 w - - 0 1",
+            // taking en passant would leave the king open along the row
+            "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+        ] {
+            walk(&mut game_from_fen(fen).unwrap(), &mm, 3);
+        }
+    }
+
+    #[test]
+    fn staged_moves() {
+        let mm = new_movement_map();
+        // the stages together have to give exactly the positions valid_games does
+        fn walk(ci: &ChessInstant, mm: &MoveMap, depth: u32) {
+            let captures = ci.capture_moves();
+            let quiets = ci.quiet_moves();
+            assert!(captures
+                .iter()
+                .all(|mv| mv.captured.is_some() || mv.promotion.is_some()));
+            assert!(quiets
+                .iter()
+                .all(|mv| mv.captured.is_none() && mv.promotion.is_none()));
+            let mut staged: Vec<ChessInstant> = captures
+                .iter()
+                .chain(quiets.iter())
+                .map(|mv| ci.apply(*mv))
+                .collect();
+            let mut valid = ci.valid_games(mm);
+            assert_eq!(valid.len(), staged.len(), "{}", ci.to_fen());
+            let key = |next: &ChessInstant| next.to_fen();
+            staged.sort_by_key(key);
+            valid.sort_by_key(key);
+            assert_eq!(valid, staged, "{}", ci.to_fen());
+
+            let evasions = ci.evasion_moves();
+            if ci.in_check(mm, &ci.king_id()) {
+                let mut evading: Vec<ChessInstant> =
+                    evasions.iter().map(|mv| ci.apply(*mv)).collect();
+                evading.sort_by_key(key);
+                assert_eq!(valid, evading, "{}", ci.to_fen());
+            } else {
+                assert!(evasions.is_empty());
+            }
+            if depth > 1 {
+                for next in &valid {
+                    walk(next, mm, depth - 1);
+                }
+            }
+        }
+        for fen in [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            walk(&game_from_fen(fen).unwrap(), &mm, 3);
+        }
+    }
+
+    #[test]
+    fn attack_maps() {
+        let game = new_chess_instant();
+        let white = game.attacks_by(&Player::P1);
+        // the third rank, the second rank and the back rank apart from the corners
+        assert_eq!(22, white.len());
+        assert!(white.contains(Square::at(5, 0)));
+        assert!(!white.contains(Square::at(4, 4)) && !white.contains(Square::at(7, 0)));
+        let map = game.attack_map();
+        assert_eq!(white, map.attacks_by(&Player::P1));
+        // f3 from the e and g pawns and the g1 knight, d2 from the queen, king, bishop and knight
+        let (f3, d2) = (Square::at(5, 5), Square::at(6, 3));
+        assert_eq!(3, map.attackers(&Player::P1, f3));
+        assert_eq!(4, map.attackers(&Player::P1, d2));
+        assert_eq!(0, map.attackers(&Player::P2, f3));
+        assert_eq!(3, map.control(f3));
+        assert_eq!(
+            map.grid(&Player::P2)[2][5],
+            map.attackers(&Player::P2, Square::at(2, 5))
+        );
+
+        // every square in the set has an attacker in the counts and the other way round
+        let kiwipete =
+            game_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let map = kiwipete.attack_map();
+        for player in [Player::P1, Player::P2] {
+            let attacks = kiwipete.attacks_by(&player);
+            for square in Square::all() {
+                let attacked = attacks.contains(square);
+                assert_eq!(attacked, map.attackers(&player, square) > 0);
+                assert_eq!(attacked, kiwipete.square_attacked(square.index(), &player));
+            }
+            assert_eq!(attacks.len(), attacks.iter().count());
+        }
+    }
+
+    #[test]
+    fn pins_and_checkers() {
+        // the rook checks along the back rank while the bishop pins the knight
+        let game = game_from_fen("4k3/8/8/8/1b6/8/3N4/r3K3 w - - 0 1").unwrap();
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        assert_eq!(vec![sq("a1")], game.checkers().iter().collect::<Vec<_>>());
+        let pins = game.pinned_pieces(&Player::P1);
+        assert_eq!(1, pins.len());
+        assert_eq!(sq("d2"), pins[0].pinned);
+        assert_eq!(sq("b4"), pins[0].pinner);
+        assert_eq!(
+            vec![sq("b4"), sq("c3")],
+            pins[0].line.iter().collect::<Vec<_>>()
+        );
+        assert!(game.pinned_pieces(&Player::P2).is_empty());
+        assert!(new_chess_instant().checkers().is_empty());
+
+        // the knight moving off the file uncovers the rook
+        let game = game_from_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+        let candidates = game.discovered_check_candidates();
+        assert_eq!(vec![sq("e4")], candidates.iter().collect::<Vec<_>>());
+        assert!(game.pinned_pieces(&Player::P2).is_empty());
+
+        // a pinned knight does not protect a pawn off its line
+        let mm = new_movement_map();
+        let free = game_from_fen("4k3/8/8/8/2P5/8/3N4/4K3 w - - 0 1").unwrap();
+        assert_eq!(vec![1], free.protectors(sq("c4"), &mm));
+        let pinned = game_from_fen("4k3/8/8/8/1bP5/8/3N4/4K3 w - - 0 1").unwrap();
+        assert!(pinned.protectors(sq("c4"), &mm).is_empty());
+    }
+
+    #[test]
+    fn see() {
+        let mm = new_movement_map();
+        let see = |fen: &str, san: &str| {
+            let game = game_from_fen(fen).unwrap();
+            game.see(game.parse_san(san, &mm).unwrap())
+        };
+        // the pawn is not defended
+        assert_eq!(
+            100,
+            see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "Rxe5")
+        );
+        // the rook and queen behind the knight join in but lose to the bishop and queen behind it
+        let xrays = "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1";
+        assert_eq!(-200, see(xrays, "Nxe5"));
+        // a queen moving onto a square a pawn guards
+        assert_eq!(-900, see("4k3/8/3p4/8/8/8/7Q/4K3 w - - 0 1", "Qe5"));
+        assert_eq!(0, see("4k3/8/3p4/8/8/8/7Q/4K3 w - - 0 1", "Qg2"));
+        // a pawn takes the rook then is taken by the king
+        assert_eq!(400, see("8/8/4k3/3r4/2P5/8/8/4K3 w - - 0 1", "cxd5"));
+        // the king can not take back while the square is still guarded
+        assert_eq!(500, see("8/8/4k3/3r4/2P5/1B6/8/4K3 w - - 0 1", "cxd5"));
+        assert_eq!(100, see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"));
+        assert_eq!(0, see("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "O-O"));
+    }
+
+    #[test]
+    fn squares() {
+        let e4: Square = "e4".parse().unwrap();
+        assert_eq!("e4", e4.to_string());
+        assert_eq!((4, 4), e4.into());
+        assert_eq!('e', e4.file().to_char());
+        assert_eq!('4', e4.rank().to_char());
+        assert_eq!(e4, Square::new(e4.file(), e4.rank()));
+        assert_eq!(Some(e4), Square::from_index(36));
+        assert_eq!(36, e4.index());
+        assert!("e9".parse::<Square>().is_err());
+        assert!("e44".parse::<Square>().is_err());
+        assert_eq!(
+            Err(ChessError::OutOfBounds { row: 8, col: 0 }),
+            Square::try_from((8, 0))
+        );
+        assert_eq!(Ok(e4), Square::try_from(36));
+        assert_eq!(Err(ChessError::IndexOutOfBounds(64)), Square::try_from(64));
+
+        // squares read from json are checked the same way
+        let json = serde_json::to_string(&e4).unwrap();
+        assert_eq!(e4, serde_json::from_str::<Square>(&json).unwrap());
+        assert!(serde_json::from_str::<Square>("200").is_err());
+        assert!(serde_json::from_str::<File>("7").is_ok());
+        assert!(serde_json::from_str::<File>("8").is_err());
+        assert!(serde_json::from_str::<Rank>("8").is_err());
+
+        let all: Vec<Square> = Square::all().collect();
+        assert_eq!(64, all.len());
+        assert_eq!("a8", all[0].to_string());
+        assert_eq!("h1", all[63].to_string());
+
+        let game = new_chess_instant();
+        let mm = new_movement_map();
+        assert_eq!(11, game.id_at("e8".parse().unwrap()));
+        assert_eq!(
+            Some((Player::P1, Piece::Queen)),
+            game.piece_at("d1".parse().unwrap())
+        );
+        let mv = game.parse_san("Nf3", &mm).unwrap();
+        assert_eq!("g1", mv.from.to_string());
+        assert_eq!("f3", mv.to.to_string());
+
+        // the old row and column signatures give the same answers
+        #[allow(deprecated)]
+        for square in Square::all() {
+            let (row, col) = square.into();
+            assert_eq!(game.id_at(square), game.board_get(&row, &col));
+            assert_eq!(game.piece_at(square), game.player_piece(&row, &col));
+            assert_eq!(
+                mm.rook_moves(square).paths,
+                mm.get_rook_moves(&row, &col).paths
+            );
+            assert_eq!(
+                game.protectors(square, &mm),
+                game.protected_by(&row, &col, &mm)
+            );
+            assert_eq!(
+                game.threats_to(square, &mm),
+                game.threatened_by(&row, &col, &mm)
+            );
+            assert_eq!(game.in_path_of(square, &mm), game.in_path(&row, &col, &mm));
+            assert_eq!(game.targets(square, &mm), game.threatens(&row, &col, &mm));
+        }
+    }
+
+    #[test]
+    fn position_builder() {
+        let sq = |name: &str| name.parse::<Square>().unwrap();
+        let setup = PositionBuilder::new()
+            .put(sq("e1"), &Player::P1, &Piece::King)
+            .put(sq("a1"), &Player::P1, &Piece::Rook)
+            .put(sq("h1"), &Player::P1, &Piece::Rook)
+            .put(sq("e4"), &Player::P1, &Piece::Pawn)
+            .put(sq("e8"), &Player::P2, &Piece::King)
+            .put(sq("d4"), &Player::P2, &Piece::Pawn)
+            .put(sq("c4"), &Player::P2, &Piece::Queen)
+            .clear(sq("c4"))
+            .side_to_move(&Player::P2)
+            .castling(&Player::P1, true, true)
+            .en_passant(Some(sq("e3")))
+            .move_counts(0, 12);
+        let built = setup.build().unwrap();
+        let fen = "4k3/8/8/8/3pP3/8/8/R3K2R b KQ e3 0 12";
+        assert_eq!(game_from_fen(fen).unwrap(), built);
+        assert_eq!(fen, built.to_fen());
+
+        let kings = PositionBuilder::new()
+            .put(sq("e1"), &Player::P1, &Piece::King)
+            .put(sq("e8"), &Player::P2, &Piece::King);
+        assert!(kings.build().is_ok());
+        assert_eq!(
+            Err(ChessError::KingCount(Player::P2, 0)),
+            kings.clear(sq("e8")).build()
+        );
+        assert_eq!(
+            Err(ChessError::InvalidCastle {
+                player: Player::P2,
+                king_side: true
+            }),
+            kings.castling(&Player::P2, false, true).build()
+        );
+        assert_eq!(
+            Err(ChessError::InvalidEnPassantSquare(sq("d6"))),
+            kings.en_passant(Some(sq("d6"))).build()
+        );
+        assert_eq!(
+            Err(ChessError::PawnOnBackRank { row: 0, col: 0 }),
+            kings.put(sq("a8"), &Player::P1, &Piece::Pawn).build()
+        );
+        // black is in check with white to move
+        assert_eq!(
+            Err(ChessError::OpponentInCheck(Player::P2)),
+            kings.put(sq("e4"), &Player::P1, &Piece::Rook).build()
+        );
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        assert_eq!(new_chess_instant(), game_from_fen(STARTING_FEN).unwrap());
+        assert_eq!(STARTING_FEN, new_chess_instant().to_fen());
+
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(kiwipete, game_from_fen(kiwipete).unwrap().to_fen());
+
+        let mm = new_movement_map();
+        let passant = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        let ci = game_from_fen(passant).unwrap();
+        assert_eq!(passant, ci.to_fen());
+        assert!(ci.legal_moves(&mm).iter().any(|mv| mv.en_passant));
+
+        // the previous move is not part of a fen
+        let after = new_chess_instant().valid_games(&mm)[0];
+        let read = ChessInstant::from_fen(&after.to_fen()).unwrap();
+        assert_eq!(after.to_fen(), read.to_fen());
+        assert_eq!(after.valid_games(&mm).len(), read.valid_games(&mm).len());
+    }
+
+    #[test]
+    fn fen_errors() {
+        assert_eq!(
+            Err(FenError::MissingField("castling")),
+            game_from_fen("8/8/8/8/8/8/8/8 w")
+        );
+        assert_eq!(
+            Err(FenError::RankLength(1)),
+            game_from_fen("4k3/8/8/8/8/8/8/4K4 w - - 0 1")
+        );
+        assert_eq!(
+            Err(FenError::InvalidPiece('x')),
+            game_from_fen("4k3/8/8/8/8/8/8/4Kx2 w - - 0 1")
+        );
+        assert_eq!(
+            Err(FenError::KingCount(Player::P2, 0)),
+            game_from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1")
+        );
+        assert_eq!(
+            Err(FenError::InvalidEnPassant("e3".to_string())),
+            game_from_fen("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1")
+        );
+        // castling king side with no rook on that side of the king
+        assert_eq!(
+            Err(FenError::InvalidCastling("Kq".to_string())),
+            game_from_fen("r3k3/8/8/8/8/8/8/R3K3 w Kq - 0 1")
+        );
+        // the en passant square is not behind a pawn, the knight would be taken in its place
+        assert_eq!(
+            Err(FenError::InvalidPosition(Box::new(
+                ChessError::InvalidEnPassantSquare(Square::at(5, 4))
+            ))),
+            game_from_fen("4k3/8/8/8/3pN3/8/8/4K3 b - e3 0 1")
+        );
+        // the rook could take the king
+        assert_eq!(
+            Err(FenError::InvalidPosition(Box::new(
+                ChessError::OpponentInCheck(Player::P2)
+            ))),
+            game_from_fen("4k3/8/8/8/8/8/8/4RK2 w - - 0 1")
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mm = new_movement_map();
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let ci = game_from_fen(kiwipete).unwrap();
+        let json = serde_json::to_string(&ci).unwrap();
+        assert!(json.starts_with("{\"board\":["));
+        let read = game_from_json_unchecked(&json);
+        assert_eq!(ci, read);
+        assert_eq!(48, read.legal_moves(&mm).len());
+    }
+
+    #[test]
+    fn json_errors() {
+        let ci = new_chess_instant();
+        let json = serde_json::to_string(&ci).unwrap();
+        assert_eq!(Ok(ci), game_from_json(&json));
+        assert!(matches!(
+            game_from_json("{\"board\":"),
+            Err(ChessError::Json(_))
+        ));
+
+        // every change is to a field the json names once
+        let bad = [
+            (
+                "\"p1_king\":60",
+                "\"p1_king\":61",
+                ChessError::InvalidKingIndex(Player::P1, 61),
+            ),
+            (
+                "\"p2_king\":4",
+                "\"p2_king\":200",
+                ChessError::InvalidKingIndex(Player::P2, 200),
+            ),
+            (
+                "\"p2_passant\":64",
+                "\"p2_passant\":65",
+                ChessError::InvalidPassant(Player::P2, 65),
+            ),
+            (
+                "\"prv_move\":[0,0]",
+                "\"prv_move\":[0,64]",
+                ChessError::InvalidPreviousMove(0, 64),
+            ),
+        ];
+        for (from, to, expected) in bad {
+            assert!(json.contains(from), "{from}");
+            assert_eq!(Err(expected), game_from_json(&json.replace(from, to)));
+        }
+        let mut board = ci.board;
+        board[2] |= 0xf << 8;
+        // rows 2 to 5 are all empty so the first match is row 2
+        let json = json.replacen(&ci.board[2].to_string(), &board[2].to_string(), 1);
+        assert_eq!(
+            Err(ChessError::InvalidSquare {
+                row: 2,
+                col: 2,
+                value: 15
+            }),
+            game_from_json(&json)
+        );
+
+        let e8 = Square::try_from((0, 4)).unwrap();
+        assert_eq!(11, ci.id_at(e8));
+        assert_eq!(Ok(11), ci.try_board_get(&0, &4));
+        assert_eq!(
+            Err(ChessError::OutOfBounds { row: 8, col: 0 }),
+            ci.try_board_get(&8, &0)
+        );
+        assert!(ci.try_player_piece(&0, &8).is_err());
+        assert_eq!(
+            ChessError::Fen(FenError::TooManyFields),
+            game_from_fen("8/8/8/8/8/8/8/8 w - - 0 1 x")
+                .unwrap_err()
+                .into()
+        );
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(Ok(()), new_chess_instant().validate());
+        // a piece put on a valid position to make one that could not come up in a game
+        let cases = [
+            (
+                "4k3/8/8/8/8/8/8/6K1 w - - 0 1",
+                (7, 4, Piece::Rook.to_id(&Player::P1)),
+                ChessError::OpponentInCheck(Player::P2),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 b - - 0 1",
+                (0, 6, Piece::Pawn.to_id(&Player::P1)),
+                ChessError::PawnOnBackRank { row: 0, col: 6 },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                (7, 0, Piece::Pawn.to_id(&Player::P2)),
+                ChessError::PawnOnBackRank { row: 7, col: 0 },
+            ),
+        ];
+        for (fen, (row, col, id), expected) in cases {
+            let mut ci = game_from_fen(fen).unwrap();
+            ci.board_set(&row, &col, id);
+            assert_eq!(Err(expected.clone()), ci.validate(), "{fen}");
+            let json = serde_json::to_string(&ci).unwrap();
+            assert_eq!(Err(expected.clone()), game_from_json(&json), "{fen}");
+            assert_eq!(
+                Err(FenError::InvalidPosition(Box::new(expected))),
+                game_from_fen(&ci.to_fen()),
+                "{fen}"
+            );
+        }
+
+        // a second white king where the queen stands
+        let mut ci = new_chess_instant();
+        ci.board_set(&7, &3, 4);
+        assert_eq!(Err(ChessError::KingCount(Player::P1, 2)), ci.validate());
+
+        // an en passant square that no pawn skipped over, after 1. Nf3
+        let mm = new_movement_map();
+        let mut passant = new_chess_instant().play_san("Nf3", &mm).unwrap();
+        passant.p1_passant = 20;
+        let json = serde_json::to_string(&passant).unwrap();
+        assert_eq!(
+            Err(ChessError::InvalidEnPassantSquare(Square::at(2, 4))),
+            game_from_json(&json)
+        );
+
+        // king side castling with the king moved to d1
+        let mut castle = new_chess_instant();
+        castle.board_set(&7, &4, 6);
+        castle.board_set(&7, &3, 4);
+        castle.p1_king = 59;
+        castle.valid_castles = (false, true, false, false);
+        let json = serde_json::to_string(&castle).unwrap();
+        assert_eq!(
+            Err(ChessError::InvalidCastle {
+                player: Player::P1,
+                king_side: true
+            }),
+            game_from_json(&json)
+        );
+
+        #[derive(serde::Deserialize)]
+        struct Request {
+            #[serde(deserialize_with = "crate::chess::deserialize_validated")]
+            game: ChessInstant,
+        }
+        let json = serde_json::to_string(&ci).unwrap();
+        let request = format!("{{\"game\":{json}}}");
+        let err = serde_json::from_str::<Request>(&request).err().unwrap();
+        assert!(err.to_string().contains("P1 has 2 kings"), "{err}");
+        // a move off the board, an illegal move and a move that does not lead to the next instant
+        let mut game = Game::new();
+        game.play(new_chess_instant().parse_san("e4", &mm).unwrap());
+        let json = serde_json::to_string(&game).unwrap();
+        let bad = json.replacen(r#""from":52"#, r#""from":99"#, 1);
+        let err = serde_json::from_str::<Game>(&bad).unwrap_err();
+        assert!(err.to_string().contains("99"), "{err}");
+        for to in [r#""to":28"#, r#""to":44"#] {
+            let bad = json.replacen(r#""to":36"#, to, 1);
+            let err = serde_json::from_str::<Game>(&bad).unwrap_err();
+            assert!(err.to_string().contains("game move 0"), "{err}");
+        }
+        let json = serde_json::to_string(&new_chess_instant()).unwrap();
+        let request = format!("{{\"game\":{json}}}");
+        let read: Request = serde_json::from_str(&request).unwrap();
+        assert_eq!(new_chess_instant(), read.game);
+    }
+
+    #[test]
+    fn san() {
+        let mm = new_movement_map();
+        // fen, san to read, san written back
+        let cases = [
+            (STARTING_FEN, "Nf3", "Nf3"),
+            (STARTING_FEN, "e4!", "e4"),
+            ("1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1", "Nbd7", "Nbd7"),
+            ("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "R1a3", "R1a3"),
+            ("1k6/8/8/8/4Q2Q/8/8/K6Q w - - 0 1", "Qh4e1", "Qh4e1"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6 e.p.", "exd6"),
+            ("r3k3/8/8/8/8/8/8/4K3 b q - 0 1", "O-O-O", "O-O-O"),
+            ("7k/4P3/8/8/8/8/8/4K3 w - - 0 1", "e8=Q+", "e8=Q+"),
+            ("7k/4P3/8/8/8/8/8/4K3 w - - 0 1", "e8N", "e8=N"),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2",
+                "Qh4",
+                "Qh4#",
+            ),
+        ];
+        for (fen, read, written) in cases {
+            let ci = game_from_fen(fen).unwrap();
+            let mv = ci.parse_san(read, &mm).unwrap();
+            assert_eq!(written, ci.to_san(mv, &mm), "{fen}");
+        }
+
+        let ci = game_from_fen("1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(
+            Err(SanError::Ambiguous {
+                san: "Nd7".to_string(),
+                candidates: vec!["Nbd7".to_string(), "Nfd7".to_string()]
+            }),
+            ci.parse_san("Nd7", &mm)
+        );
+        let ci = new_chess_instant();
+        for illegal in ["Ke2", "e5", "O-O", "Nxf3", "exd3"] {
+            assert_eq!(
+                Err(SanError::Illegal(illegal.to_string())),
+                ci.parse_san(illegal, &mm)
+            );
+        }
+        for invalid in ["", "Zz9", "e9", "Nbbd7", "e8=K"] {
+            assert_eq!(
+                Err(SanError::Invalid(invalid.to_string())),
+                ci.parse_san(invalid, &mm)
+            );
+        }
+
+        // every move writes a san that is unique in the position and reads back as the same move
+        for fen in [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ] {
+            let ci = game_from_fen(fen).unwrap();
+            let moves = ci.legal_moves(&mm);
+            let sans: HashSet<String> = moves.iter().map(|mv| ci.to_san(*mv, &mm)).collect();
+            assert_eq!(moves.len(), sans.len(), "{fen}");
+            for mv in moves {
+                assert_eq!(Ok(mv), ci.parse_san(&ci.to_san(mv, &mm), &mm), "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn uci() {
+        let mm = new_movement_map();
+        let ci = new_chess_instant();
+        let next = ci.play_uci("e2e4", &mm).unwrap();
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            next.to_fen()
+        );
+        assert_eq!(
+            Err(UciError::Illegal("e2e5".to_string())),
+            ci.play_uci("e2e5", &mm)
+        );
+        for invalid in ["", "e2", "e2e4qq", "i2e4", "e2e9", "e7e8k"] {
+            assert_eq!(
+                Err(UciError::Invalid(invalid.to_string())),
+                Move::from_uci(invalid, &ci, &mm)
+            );
+        }
+
+        let ci = game_from_fen("n3k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        let promotion = Move::from_uci("b7a8n", &ci, &mm).unwrap();
+        assert_eq!(Some(Piece::Knight), promotion.promotion);
+        assert_eq!("b7a8n", promotion.to_uci());
+        assert!(Move::from_uci("b7b8", &ci, &mm).is_err());
+        // castling is read as the king moving two squares or taking its own rook
+        for (uci, written) in [("e1g1", "e1g1"), ("e1h1", "e1g1"), ("e1a1", "e1c1")] {
+            let castle = Move::from_uci(uci, &ci, &mm).unwrap();
+            assert!(castle.castle);
+            assert_eq!(written, castle.to_uci());
+        }
+
+        // every legal move reads back from its own uci string
+        let ci =
+            game_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        for mv in ci.legal_moves(&mm) {
+            assert_eq!(Ok(mv), Move::from_uci(&mv.to_uci(), &ci, &mm));
+        }
+    }
+
+    #[test]
+    fn pgn_read() {
+        let mm = new_movement_map();
+        let pgn = r#"
+[Event "Club \"Night\""]
+[White "Ann"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 e5 {the open game} 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 $1
+3.Bb5 a6 ; the morphy defence
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 1-0
+
+[Event "Scholar"]
+[SetUp "1"]
+[FEN "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"]
+
+2. Bc4 Nc6 3. Qh5 Nf6?? 4. Qxf7# 1-0
+% an escaped line
+1. d4 d5 *
+"#;
+        let games = read_pgn(pgn, &mm).unwrap();
+        assert_eq!(3, games.len());
+
+        let ruy = &games[0];
+        assert_eq!(Some("Club \"Night\""), ruy.tag("Event"));
+        assert_eq!(Some("Bob"), ruy.tag("Black"));
+        assert_eq!(4, ruy.tags().len());
+        assert_eq!("1-0", ruy.result());
+        assert_eq!(17, ruy.instants().len());
+        assert_eq!(
+            "r1bq1rk1/2p1bppp/p1np1n2/1p2p3/4P3/1BP2N2/PP1P1PPP/RNBQR1K1 w - - 1 9",
+            ruy.game().current().to_fen()
+        );
+        assert_eq!(
+            &[
+                (2, "the open game".to_string()),
+                (6, "the morphy defence".to_string())
+            ],
+            ruy.comments()
+        );
+        assert_eq!(&[(4, 1)], ruy.nags());
+
+        let scholar = &games[1];
+        assert_eq!(5, scholar.game().moves().len());
+        assert_eq!(
+            GameStatus::Checkmate { winner: Player::P1 },
+            scholar.game().status(&mm)
+        );
+        assert_eq!("*", games[2].result());
+        assert_eq!(3, games[2].instants().len());
+
+        let illegal =
+            "[Event \"?\"]\n\n1. e4 e5 2. Ke2 *\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 Ke7 4. Bxc6 Bb4 *";
+        assert_eq!(
+            Err(PgnError::IllegalMove {
+                game: 2,
+                ply: 8,
+                token: "Bb4".to_string(),
+                reason: SanError::Illegal("Bb4".to_string())
+            }),
+            read_pgn(illegal, &mm)
+        );
+        assert_eq!(
+            Err(PgnError::UnbalancedVariation),
+            read_pgn("1. e4 (1. d4 e5 *", &mm)
+        );
+        assert_eq!(
+            Err(PgnError::UnterminatedComment),
+            read_pgn("1. e4 {never closed", &mm)
+        );
+        assert!(matches!(
+            read_pgn("[Event]\n1. e4 *", &mm),
+            Err(PgnError::InvalidTag(_))
+        ));
+    }
+
+    #[test]
+    fn pgn_write() {
+        let mm = new_movement_map();
+        // two bots play a few moves against each other
+        let bots = [
+            bot_1::BotSettings::new_depth(1),
+            bot_1::BotSettings::new_depth(0),
+        ];
+        let mut game = Game::new();
+        let mut evals = Vec::new();
+        for ply in 0..6 {
+            let (next, score) = bot_1::bot_1_moves(game.current(), &mm, &bots[ply % 2])[0];
+            let mv = game
+                .current()
+                .legal_moves(&mm)
+                .into_iter()
+                .find(|mv| game.current().apply(*mv) == next)
+                .unwrap();
+            game.play(mv);
+            evals.push(Some(score));
+        }
+        let mut tags = pgn::bot_tags(&Player::P1, "bot one", bots[0].search_depth());
+        tags.extend(pgn::bot_tags(
+            &Player::P2,
+            "bot \"zero\"",
+            bots[1].search_depth(),
+        ));
+        tags.push(("Event".to_string(), "Bot Match".to_string()));
+        let written = write_pgn(&game, &tags, &evals, &mm);
+        assert!(written.starts_with("[Event \"Bot Match\"]\n[Site \"?\"]\n"));
+        assert!(written.contains("[Black \"bot \\\"zero\\\"\"]\n[Result \"*\"]\n"));
+        assert!(written.contains("[WhiteSearchDepth \"1\"]\n[BlackSearchDepth \"0\"]\n"));
+        assert!(written.contains("[%eval "));
+        assert!(written.trim_end().ends_with(" *"));
+        assert!(written.lines().all(|line| line.len() <= 80));
+
+        let read = &read_pgn(&written, &mm).unwrap()[0];
+        assert_eq!(game.instants(), read.instants());
+        assert_eq!(Some("bot one"), read.tag("White"));
+        assert_eq!(Some("1"), read.tag("WhiteSearchDepth"));
+        assert_eq!(6, read.comments().len());
+
+        // a finished game from a set up position with black to move
+        let start =
+            game_from_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2").unwrap();
+        let mut game = Game::from_instant(start);
+        game.play(start.parse_san("Qh4", &mm).unwrap());
+        let written = write_pgn(&game, &[], &[], &mm);
+        assert_eq!(
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+             [White \"?\"]\n[Black \"?\"]\n[Result \"0-1\"]\n[SetUp \"1\"]\n\
+             [FEN \"rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2\"]\n\
+             \n2... Qh4# 0-1\n",
+            written
+        );
+        let read = &read_pgn(&written, &mm).unwrap()[0];
+        assert_eq!(game.instants(), read.instants());
+        assert_eq!("0-1", read.result());
+
+        // mate scores from the search are written as moves to mate from white's view
+        let bot = BotSettings::new_depth(2);
+        let hf = heuristic::heuristic_v1::heuristic_fn;
+        let scored = |sans: &[&str]| {
+            let mut game = Game::new();
+            let mut evals = Vec::new();
+            for san in sans {
+                let ci = *game.current();
+                let mv = ci.parse_san(san, &mm).unwrap();
+                let graded = minimax(&mm, &bot, &ci, hf);
+                let (_, score) = graded
+                    .iter()
+                    .find(|(next, _)| *next == ci.apply(mv))
+                    .unwrap();
+                evals.push(Some(*score));
+                game.play(mv);
+            }
+            // joined back up as the export wraps lines at 80 characters
+            write_pgn(&game, &[], &evals, &mm).replace('\n', " ")
+        };
+        // white mates with the move, black allowed it with the move before
+        let written = scored(&["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6", "Qxf7"]);
+        assert!(
+            written.contains(" Nf6 {[%eval #1]} 4. Qxf7# {[%eval #0]} 1-0"),
+            "{written}"
+        );
+        // black mates with the move, white allowed it with the move before
+        let written = scored(&["f3", "e5", "g4", "Qh4"]);
+        assert!(
+            written.contains(" 2. g4 {[%eval #-1]} Qh4# {[%eval #-0]} 0-1"),
+            "{written}"
+        );
+        // other scores are turned to white's view as well
+        let written = write_pgn(&game, &[], &[Some(250)], &mm);
+        assert!(
+            written.contains("2... Qh4# {[%eval -2.50]} 0-1"),
+            "{written}"
+        );
+    }
+
+    #[test]
+    fn halfmove_clock() {
+        let mm = new_movement_map();
+        let fen = "4k3/8/8/8/8/8/4P3/R3K3 w Q - 98 60";
+        let ci = game_from_fen(fen).unwrap();
+        assert_eq!(fen, ci.to_fen());
+        assert!(!ci.is_fifty_move_draw());
+
+        let moves = ci.legal_moves(&mm);
+        let rook = moves.iter().find(|mv| mv.piece == Piece::Rook).unwrap();
+        let pawn = moves.iter().find(|mv| mv.piece == Piece::Pawn).unwrap();
+        let after_rook = ci.apply(*rook);
+        assert_eq!(99, after_rook.halfmove_clock());
+        assert_eq!(0, ci.apply(*pawn).halfmove_clock());
+
+        let king = after_rook
+            .legal_moves(&mm)
+            .into_iter()
+            .find(|mv| mv.piece == Piece::King)
+            .unwrap();
+        let drawn = after_rook.apply(king);
+        assert!(drawn.is_fifty_move_draw());
+        assert!(!drawn.is_seventy_five_move_draw());
+        assert!(game_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 150 120")
+            .unwrap()
+            .is_seventy_five_move_draw());
+
+        // the clock is saved with the game and older saves without it start at 0
+        let json = serde_json::to_string(&drawn).unwrap();
+        assert_eq!(drawn, game_from_json_unchecked(&json));
+        let old = json.replace(",\"halfmove\":100", "");
+        assert_ne!(json, old);
+        assert_eq!(0, game_from_json_unchecked(&old).halfmove_clock());
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let dead = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2b1K3 b - - 0 1",
+            // every bishop on a dark square
+            "4k3/8/8/8/8/8/7B/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/b7/8/2B1K3 w - - 0 1",
+        ];
+        for fen in dead {
+            assert!(
+                game_from_fen(fen).unwrap().is_insufficient_material(),
+                "{fen}"
+            );
+        }
+        let alive = [
+            STARTING_FEN,
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K1n1 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1KB1b w - - 0 1",
+        ];
+        for fen in alive {
+            assert!(
+                !game_from_fen(fen).unwrap().is_insufficient_material(),
+                "{fen}"
+            );
+        }
+
+        // taking the last pawn leaves the bot nothing to play for
+        let mm = new_movement_map();
+        let ci = game_from_fen("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1").unwrap();
+        let bot = BotSettings::new_depth(2);
+        let hf = heuristic::heuristic_v1::heuristic_fn;
+        let (best, score) = minimax(&mm, &bot, &ci, hf)[0];
+        assert!(best.is_insufficient_material());
+        assert_eq!(0, score);
+    }
+
+    /// a move given as the (row, col) it is made from and the (row, col) it goes to
+    type Step = ((usize, u32), (usize, u32));
+
+    /// knights out and back again, each cycle comes back to the position it started from
+    const KNIGHT_CYCLE: [Step; 4] = [
+        ((7, 6), (5, 5)),
+        ((0, 6), (2, 5)),
+        ((5, 5), (7, 6)),
+        ((2, 5), (0, 6)),
+    ];
+
+    /// the legal move in the position for the step, panics when there is none
+    fn legal_move(ci: &ChessInstant, mm: &MoveMap, (from, to): Step) -> Move {
+        ci.legal_moves(mm)
+            .into_iter()
+            .find(|mv| mv.from == Square::at(from.0, from.1) && mv.to == Square::at(to.0, to.1))
+            .unwrap()
+    }
+
+    /// plays each of the steps in the game
+    fn play_steps(game: &mut Game, mm: &MoveMap, steps: &[Step]) {
+        for step in steps {
+            game.play(legal_move(game.current(), mm, *step));
+        }
+    }
+
+    #[test]
+    fn repetition() {
+        let mm = new_movement_map();
+        let mut game = Game::new();
+        for i in 1..=4 {
+            play_steps(&mut game, &mm, &KNIGHT_CYCLE);
+            assert_eq!(i + 1, game.repetitions());
+            assert_eq!(i >= 2, game.is_threefold_repetition());
+            assert_eq!(i >= 4, game.is_fivefold_repetition());
+        }
+        assert_eq!(17, game.instants().len());
+        assert_eq!(16, game.moves().len());
+        let last = game.undo().unwrap();
+        assert_eq!((Square::at(2, 5), Square::at(0, 6)), (last.from, last.to));
+        assert_eq!(4, game.repetitions());
+
+        // a saved game is checked when it is read back
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(game, serde_json::from_str::<Game>(&json).unwrap());
+        let err = serde_json::from_str::<Game>(r#"{"instants":[],"moves":[]}"#).unwrap_err();
+        assert!(err.to_string().contains("0 instants for 0 moves"), "{err}");
+        let mut king = new_chess_instant();
+        king.board_set(&7, &3, 4);
+        let json = serde_json::to_string(&Game::from_instant(king)).unwrap();
+        let err = serde_json::from_str::<Game>(&json).unwrap_err();
+        assert!(err.to_string().contains("P1 has 2 kings"), "{err}");
+
+        // down a queen the only way for black to save the game is to repeat the position
+        let start = game_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let mut game = Game::from_instant(start);
+        play_steps(
+            &mut game,
+            &mm,
+            &[((7, 3), (6, 3)), ((0, 4), (1, 4)), ((6, 3), (7, 3))],
+        );
+        let bot = BotSettings::new_depth(0);
+        let hf = heuristic::heuristic_v1::heuristic_fn;
+        let (best, score) = minimax_game(&mm, &bot, &game, hf)[0];
+        assert_eq!(start.hash_key(), best.hash_key());
+        assert_eq!(0, score);
+        assert!(minimax(&mm, &bot, game.current(), hf)[0].1 < 0);
+    }
+
+    #[test]
+    fn status() {
+        let mm = new_movement_map();
+        let cases = [
+            (STARTING_FEN, GameStatus::Ongoing),
+            // fool's mate
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+                GameStatus::Checkmate { winner: Player::P2 },
+            ),
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", GameStatus::Stalemate),
+            (
+                "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+                GameStatus::InsufficientMaterial,
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R3K3 w - - 100 80",
+                GameStatus::FiftyMoveRule,
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R3K3 w - - 150 120",
+                GameStatus::SeventyFiveMoveRule,
+            ),
+            // mate on the hundredth halfmove still wins
+            (
+                "R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80",
+                GameStatus::Checkmate { winner: Player::P1 },
+            ),
+        ];
+        for (fen, expected) in cases {
+            let status = game_from_fen(fen).unwrap().status(&mm);
+            assert_eq!(expected, status, "{fen}");
+            let claimable = expected == GameStatus::FiftyMoveRule;
+            assert_eq!(claimable, status.can_claim_draw(), "{fen}");
+            assert_eq!(
+                expected != GameStatus::Ongoing && !claimable,
+                status.is_over(),
+                "{fen}"
+            );
+        }
+        assert_eq!(
+            Some(Player::P2),
+            game_from_fen(cases[1].0).unwrap().status(&mm).winner()
+        );
+        assert!(GameStatus::Stalemate.is_draw());
+        assert!(!GameStatus::Checkmate { winner: Player::P1 }.is_draw());
+
+        let json = serde_json::to_string(&GameStatus::Checkmate { winner: Player::P1 }).unwrap();
+        let back: GameStatus = serde_json::from_str(&json).unwrap();
+        assert_eq!(GameStatus::Checkmate { winner: Player::P1 }, back);
+
+        // knights out and back, each cycle gives the start position once more
+        let mut game = Game::new();
+        for i in 1..=4 {
+            play_steps(&mut game, &mm, &KNIGHT_CYCLE);
+            let expected = match i {
+                1 => GameStatus::Ongoing,
+                2 | 3 => GameStatus::Repetition,
+                _ => GameStatus::FivefoldRepetition,
             };
+            assert_eq!(expected, game.status(&mm));
+        }
+        assert!(game.status(&mm).is_over());
+        assert_eq!(GameStatus::Ongoing, game.current().status(&mm));
+    }
+
+    #[test]
+    fn zobrist_transpositions() {
+        let mm = new_movement_map();
+        // plays the moves from the start position and gives back the position they lead to
+        let play = |steps: &[Step]| {
+            let mut ci = new_chess_instant();
+            for step in steps {
+                ci.make_move(legal_move(&ci, &mm, *step));
+            }
+            ci
+        };
+        let e4 = ((6, 4), (4, 4));
+        let e5 = ((1, 4), (3, 4));
+        let nf3 = ((7, 6), (5, 5));
+        let nc6 = ((0, 1), (2, 2));
+        let a = play(&[e4, e5, nf3, nc6]);
+        let b = play(&[nf3, nc6, e4, e5]);
+        assert_eq!(a.hash_key(), b.hash_key());
+
+        // knights out and back again is the start position with the same player to move
+        let ng1 = ((5, 5), (7, 6));
+        let nb8 = ((2, 2), (0, 1));
+        let back = play(&[nf3, nc6, ng1, nb8]);
+        assert_eq!(new_chess_instant().hash_key(), back.hash_key());
+        assert_ne!(back.hash_key(), play(&[nf3, nc6, ng1]).hash_key());
 
-            assert_eq!(check, checkmates);
+        // en passant that can not be taken does not change the key
+        let d4 = ((6, 3), (4, 3));
+        let d3 = ((6, 3), (5, 3));
+        let d3_d4 = ((5, 3), (4, 3));
+        let a5 = ((1, 0), (3, 0));
+        let a6 = ((1, 0), (2, 0));
+        let a6_a5 = ((2, 0), (3, 0));
+        let a = play(&[d4, a5]);
+        let b = play(&[d3, a6, d3_d4, a6_a5]);
+        assert_eq!(a.hash_key(), b.hash_key());
+    }
+
+    #[test]
+    fn zobrist_incremental() {
+        // the key kept by make_move has to match the key worked out from scratch
+        fn walk(ci: &mut ChessInstant, mm: &MoveMap, depth: u32) {
+            let fresh = ChessInstant::from_fen(&ci.to_fen()).unwrap();
+            assert_eq!(fresh.hash_key(), ci.hash_key());
+            if depth == 0 {
+                return;
+            }
+            for mv in ci.legal_moves(mm) {
+                let key = ci.hash_key();
+                let undo = ci.make_move(mv);
+                walk(ci, mm, depth - 1);
+                ci.unmake_move(undo);
+                assert_eq!(key, ci.hash_key());
+            }
+        }
+        let mm = new_movement_map();
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        walk(&mut game_from_fen(kiwipete).unwrap(), &mm, 3);
+    }
+
+    #[test]
+    fn make_unmake() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mm = new_movement_map();
+        let mut ci = game_from_fen(kiwipete).unwrap();
+        let start = ci;
+        for mv in start.legal_moves(&mm) {
+            let undo = ci.make_move(mv);
+            assert_eq!(start.apply(mv), ci);
+            for reply in ci.legal_moves(&mm) {
+                let before = ci;
+                let reply_undo = ci.make_move(reply);
+                ci.unmake_move(reply_undo);
+                assert_eq!(before, ci);
+            }
+            ci.unmake_move(undo);
+            assert_eq!(start, ci);
+        }
+    }
+
+    #[test]
+    fn chess960() {
+        let mm = new_movement_map();
+        // counts the leaves with make and unmake, checking unmake gives back the same instant
+        fn perft(ci: &mut ChessInstant, mm: &MoveMap, depth: u32) -> u64 {
+            if depth == 0 {
+                return 1;
+            }
+            let mut count = 0;
+            for mv in ci.legal_moves(mm) {
+                let before = *ci;
+                let undo = ci.make_move(mv);
+                assert_eq!(ci.hash_key(), ci.compute_hash());
+                count += perft(ci, mm, depth - 1);
+                ci.unmake_move(undo);
+                assert_eq!(before, *ci);
+            }
+            count
+        }
+        // published chess960 perft results
+        let cases = [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                [21, 528, 12189],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18002],
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                [20, 479, 10471],
+            ),
+            (
+                "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+                [22, 593, 13440],
+            ),
+            (
+                "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+                [28, 1120, 31058],
+            ),
+        ];
+        for (fen, counts) in cases {
+            let mut ci = game_from_fen(fen).unwrap();
+            assert!(ci.is_chess960());
+            // the fen written back uses KQkq where the rook is the outermost one
+            let again = game_from_fen(&ci.to_fen()).unwrap();
+            assert_eq!(ci.legal_moves(&mm), again.legal_moves(&mm));
+            for (depth, count) in counts.into_iter().enumerate() {
+                assert_eq!(count, perft(&mut ci, &mm, depth as u32 + 1), "{fen}");
+            }
+        }
+
+        assert_eq!(
+            STARTING_FEN,
+            ChessInstant::new_chess960(518).unwrap().to_fen()
+        );
+        assert_eq!(
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1",
+            ChessInstant::new_chess960(0).unwrap().to_fen()
+        );
+        assert_eq!(
+            "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1",
+            ChessInstant::new_chess960(959).unwrap().to_fen()
+        );
+        assert_eq!(
+            Err(ChessError::Chess960Number(960)),
+            ChessInstant::new_chess960(960)
+        );
+
+        // the king on g1 castles king side without moving, the rook still jumps to f1
+        let ci = game_from_fen("1r4k1/8/8/8/8/8/8/1R4KR w Hb - 0 1").unwrap();
+        let castle = ci.parse_san("O-O", &mm).unwrap();
+        assert_eq!(castle.from, castle.to);
+        assert_eq!("g1h1", ci.move_to_uci(castle));
+        assert_eq!(Ok(castle), Move::from_uci("g1h1", &ci, &mm));
+        let next = ci.apply(castle);
+        assert_eq!("1r4k1/8/8/8/8/8/8/1R3RK1 b q - 1 1", next.to_fen());
+        // castling queen side the king lands on c8 past the b8 rook which ends on d8
+        let ci = game_from_fen("1r4k1/8/8/8/8/8/8/6K1 b b - 0 1").unwrap();
+        let next = ci.play_san("O-O-O", &mm).unwrap();
+        assert_eq!("2kr4/8/8/8/8/8/8/6K1 w - - 1 2", next.to_fen());
+        // the king lands on c1 where the b1 rook no longer blocks the a1 rook
+        let ci = game_from_fen("4k3/8/8/8/8/8/8/rR2K3 w B - 0 1").unwrap();
+        assert!(ci.parse_san("O-O-O", &mm).is_err());
+    }
+
+    #[test]
+    fn six_plie() {
+        let time = std::time::Instant::now();
+        let game = new_chess_instant();
+        // nodes, captures, en passants, castles, promotions, checks and checkmates at each depth
+        let table = [
+            (20, 0, 0, 0, 0, 0, 0),
+            (400, 0, 0, 0, 0, 0, 0),
+            (8_902, 34, 0, 0, 0, 12, 0),
+            (197_281, 1_576, 0, 0, 0, 469, 8),
+            (4_865_609, 82_719, 258, 0, 0, 27_351, 347),
+            (119_060_324, 2_812_008, 5_248, 0, 0, 809_099, 10_828),
+        ];
+        for (depth, expected) in (1..).zip(table) {
+            let counts = perft::perft(&game, depth);
+            let found = (
+                counts.nodes,
+                counts.captures,
+                counts.en_passants,
+                counts.castles,
+                counts.promotions,
+                counts.checks,
+                counts.checkmates,
+            );
+            assert_eq!(expected, found, "depth {depth}");
         }
         println!(
             "six plie competed in: {} milliseconds.",
             time.elapsed().as_millis()
         );
     }
+
+    /// the standard perft positions with the nodes counted at a depth
+    const PERFT_POSITIONS: [(&str, u32, u64); 23] = [
+        // kiwipete, castling through check and pins on both sides
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+            97_862,
+        ),
+        // position 3, en passant taking off a pin along the row
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674_624),
+        // position 4 and the same with the colours swapped, promotions and castling out of it
+        (
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            3,
+            9_467,
+        ),
+        (
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            3,
+            9_467,
+        ),
+        // position 5, a pawn about to promote with the king castling
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            3,
+            62_379,
+        ),
+        // position 6, a quiet middle game
+        (
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            3,
+            89_890,
+        ),
+        // taking en passant would leave the king in check
+        ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6, 1_134_888),
+        ("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6, 1_015_133),
+        ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6, 1_440_467),
+        // en passant gives check
+        ("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1", 6, 824_064),
+        // castling gives check
+        ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 6, 661_072),
+        ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 6, 803_711),
+        // castling rights lost and castling blocked by attacks
+        ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1_274_206),
+        ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1_720_476),
+        // promotion out of check, to give check and under promotion to give check
+        ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6, 3_821_001),
+        ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 6, 217_342),
+        ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6, 92_683),
+        // every pawn promoting on both sides
+        ("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", 5, 3_605_103),
+        // discovered check
+        ("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 5, 1_004_658),
+        // stalemates and checkmates
+        ("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6, 2_217),
+        ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7, 567_584),
+        ("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 4, 23_527),
+        // a double check only the king can answer
+        ("7k/8/8/3R4/1b6/8/8/r3K3 w - - 0 1", 1, 2),
+    ];
+
+    #[test]
+    fn perft_positions() {
+        for (fen, depth, nodes) in PERFT_POSITIONS {
+            let game = game_from_fen(fen).unwrap();
+            assert_eq!(nodes, perft::perft(&game, depth).nodes, "{fen}");
+        }
+    }
+
+    // run with: cargo test --release -- --ignored perft_positions_deep
+    #[test]
+    #[ignore]
+    fn perft_positions_deep() {
+        let positions = [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                5,
+                193_690_690,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 7, 178_633_661),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                5,
+                15_833_292,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                5,
+                89_941_194,
+            ),
+            (
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                5,
+                164_075_551,
+            ),
+            ("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", 6, 71_179_139),
+        ];
+        for (fen, depth, nodes) in positions {
+            let game = game_from_fen(fen).unwrap();
+            assert_eq!(nodes, perft::perft(&game, depth).nodes, "{fen}");
+        }
+    }
+
+    #[test]
+    fn perft_divide() {
+        let game =
+            game_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let divided = perft::perft_divide(&game, 2);
+        assert_eq!(48, divided.len());
+        let mut total = perft::PerftCounts::default();
+        for (_, counts) in &divided {
+            total += *counts;
+        }
+        assert_eq!(perft::perft(&game, 2), total);
+        assert_eq!(2_039, total.nodes);
+        let castle = divided
+            .iter()
+            .find(|(mv, _)| game.move_to_uci(*mv) == "e1g1")
+            .unwrap();
+        assert_eq!(43, castle.1.nodes);
+        assert_eq!(1, perft::perft(&game, 0).nodes);
+        assert!(perft::perft_divide(&game, 0).is_empty());
+        assert_eq!(48, perft::perft_divide(&game, 1).len());
+    }
 }
@@ -1,5 +1,7 @@
 use crate::chess::ChessInstant;
+use crate::game::{self, Game};
 use crate::movemap::MoveMap;
+use crate::status::GameStatus;
 
 pub struct BotSettings {
     pub search_depth: i32,
@@ -38,6 +40,33 @@ impl BotSettings {
     }
 }
 
+/// the alpha beta window of a search node and the keys of every position played before it
+pub(crate) struct SearchState<'a> {
+    pub(crate) alpha: i32,
+    pub(crate) beta: i32,
+    pub(crate) history: &'a mut Vec<u64>,
+}
+
+impl<'a> SearchState<'a> {
+    /// the state at the root of a search, with the full window
+    pub(crate) fn new(history: &'a mut Vec<u64>) -> SearchState<'a> {
+        SearchState {
+            alpha: i32::MIN,
+            beta: i32::MAX,
+            history,
+        }
+    }
+
+    /// the state for a child node, the child narrowing its window does not change this one
+    pub(crate) fn child(&mut self) -> SearchState<'_> {
+        SearchState {
+            alpha: self.alpha,
+            beta: self.beta,
+            history: self.history,
+        }
+    }
+}
+
 /// returns a score for every possible move that could be made for the given chess instant,
 /// if no valid moves returns an empty vector,
 /// the heuristic fn has to calculate the value for the current player for the given chess instant
@@ -47,22 +76,47 @@ pub fn minimax(
     root: &ChessInstant,
     heuristic_fn: fn(&ChessInstant, &MoveMap, &BotSettings) -> i32,
 ) -> Vec<(ChessInstant, i32)> {
-    let l1_moves = root.valid_games(mm);
+    minimax_history(mm, bot, root, &mut Vec::new(), heuristic_fn)
+}
+
+/// the same as minimax for the current position of the game, but any position that already
+/// came up in the game or comes up again in the search is scored as a draw
+pub fn minimax_game(
+    mm: &MoveMap,
+    bot: &BotSettings,
+    game: &Game,
+    heuristic_fn: fn(&ChessInstant, &MoveMap, &BotSettings) -> i32,
+) -> Vec<(ChessInstant, i32)> {
+    let mut history = game.history_keys();
+    history.pop(); // the current position is the root of the search
+    minimax_history(mm, bot, game.current(), &mut history, heuristic_fn)
+}
+
+/// minimax where history holds the keys of every position played before root
+fn minimax_history(
+    mm: &MoveMap,
+    bot: &BotSettings,
+    root: &ChessInstant,
+    history: &mut Vec<u64>,
+    heuristic_fn: fn(&ChessInstant, &MoveMap, &BotSettings) -> i32,
+) -> Vec<(ChessInstant, i32)> {
+    let mut ci = *root;
     let mut graded = Vec::new();
-    for l1ci in l1_moves {
-        graded.push((
-            l1ci.clone(),
-            minmax_sub(
-                &l1ci,
-                mm,
-                bot,
-                bot.search_depth,
-                false,
-                i32::MIN,
-                i32::MAX,
-                heuristic_fn,
-            ),
-        ));
+    for mv in root.legal_moves(mm) {
+        history.push(ci.hash_key());
+        let undo = ci.make_move(mv);
+        let score = minmax_sub(
+            &mut ci,
+            mm,
+            bot,
+            bot.search_depth,
+            false,
+            SearchState::new(history),
+            heuristic_fn,
+        );
+        ci.unmake_move(undo);
+        history.pop();
+        graded.push((root.apply(mv), score));
     }
     graded.sort_unstable_by(|(_, a), (_, b)| b.cmp(a)); // sort max to min
 
@@ -70,15 +124,33 @@ pub fn minimax(
 
     // recursive min max fuction
     fn minmax_sub(
-        ci: &ChessInstant,
+        ci: &mut ChessInstant,
         mm: &MoveMap,
         bot: &BotSettings,
         depth: i32,
         maximizing: bool,
-        mut alpha: i32,
-        mut beta: i32,
+        mut search: SearchState,
         heuristic_fn: fn(&ChessInstant, &MoveMap, &BotSettings) -> i32,
     ) -> i32 {
+        if game::is_repeat(ci, search.history) {
+            return 0; // score for a draw
+        }
+        // legality is only worked out for the moves the search gets to before a cutoff
+        let moves = ci.pseudo_legal_moves();
+        let legality = ci.legality();
+        let has_moves = moves.iter().any(|mv| ci.is_legal_with(*mv, &legality));
+        match ci.status_with_moves(mm, has_moves) {
+            GameStatus::Ongoing => {}
+            GameStatus::Checkmate { .. } => {
+                // the player to move lost
+                if maximizing {
+                    return i32::MIN + bot.search_depth - depth;
+                } else {
+                    return i32::MAX - bot.search_depth + depth;
+                }
+            }
+            _ => return 0, // score for a draw
+        }
         if depth == 0 {
             // the heuristic value of the game
             if maximizing {
@@ -90,40 +162,41 @@ pub fn minimax(
             }
         }
 
-        let ci_children = ci.valid_games(mm);
-        if ci_children.len() == 0 {
-            if !ci.in_check(mm, &ci.king_id()) {
-                return 0; // score for a draw
-            }
-            // else checkmate
-            if maximizing {
-                return i32::MIN + bot.search_depth - depth
-            } else {
-                return i32::MAX - bot.search_depth + depth
-            }
-        }
         if maximizing {
             let mut value = i32::MIN; // + bot.search_depth - depth;
-            for child in ci_children {
-                let score =
-                    minmax_sub(&child, mm, bot, depth - 1, false, alpha, beta, heuristic_fn);
+            for mv in moves {
+                if !ci.is_legal_with(mv, &legality) {
+                    continue;
+                }
+                search.history.push(ci.hash_key());
+                let undo = ci.make_move(mv);
+                let score = minmax_sub(ci, mm, bot, depth - 1, false, search.child(), heuristic_fn);
+                ci.unmake_move(undo);
+                search.history.pop();
                 value = value.max(score); // maximize score
-                if value > beta {
+                if value > search.beta {
                     break; // beta cutoff
                 }
 
-                alpha = if alpha > value { alpha } else { value };
+                search.alpha = search.alpha.max(value);
             }
             return value;
         } else {
             let mut value = i32::MAX; // - bot.search_depth + depth;
-            for child in ci_children {
-                let score = minmax_sub(&child, mm, bot, depth - 1, true, alpha, beta, heuristic_fn);
+            for mv in moves {
+                if !ci.is_legal_with(mv, &legality) {
+                    continue;
+                }
+                search.history.push(ci.hash_key());
+                let undo = ci.make_move(mv);
+                let score = minmax_sub(ci, mm, bot, depth - 1, true, search.child(), heuristic_fn);
+                ci.unmake_move(undo);
+                search.history.pop();
                 value = value.min(score); // minimize score
-                if value < alpha {
+                if value < search.alpha {
                     break; // alpha cutoff
                 }
-                beta = if beta < value { beta } else { value };
+                search.beta = search.beta.min(value);
             }
             return value;
         }
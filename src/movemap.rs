@@ -1,3 +1,5 @@
+use crate::square::Square;
+
 pub struct MoveMap {
     row_col: Vec<(usize, u32)>,
     rook: Vec<Vec<MovePaths>>,
@@ -25,39 +27,73 @@ pub struct MovePaths {
 }
 
 impl MoveMap {
+    #[deprecated(note = "use Square::all")]
     pub fn locations(&self) -> &Vec<(usize, u32)> {
         &self.row_col
     }
+    #[deprecated(note = "use king_moves with a Square")]
     pub fn get_king_moves(&self, row: &usize, col: &u32) -> &MovePaths {
-        &self.king[*row][*col as usize]
+        self.king_moves(MoveMap::square(row, col))
     }
+    #[deprecated(note = "use knight_moves with a Square")]
     pub fn get_knight_moves(&self, row: &usize, col: &u32) -> &MovePaths {
-        &self.knight[*row][*col as usize]
+        self.knight_moves(MoveMap::square(row, col))
     }
+    #[deprecated(note = "use queen_moves with a Square")]
     pub fn get_queen_moves(&self, row: &usize, col: &u32) -> &MovePaths {
-        &self.queen[*row][*col as usize]
+        self.queen_moves(MoveMap::square(row, col))
     }
+    #[deprecated(note = "use bishop_moves with a Square")]
     pub fn get_bishop_moves(&self, row: &usize, col: &u32) -> &MovePaths {
-        &self.bishop[*row][*col as usize]
+        self.bishop_moves(MoveMap::square(row, col))
     }
+    #[deprecated(note = "use rook_moves with a Square")]
     pub fn get_rook_moves(&self, row: &usize, col: &u32) -> &MovePaths {
-        &self.rook[*row][*col as usize]
+        self.rook_moves(MoveMap::square(row, col))
     }
+    #[deprecated(note = "use pawn_moves with a Square")]
     pub fn get_pawn_moves(&self, row: &usize, col: &u32, piece_id: &u32) -> &PawnMoveMap {
+        self.pawn_moves(MoveMap::square(row, col), piece_id)
+    }
+    /// returns all the ways the piece at the row and col could be attacked
+    #[deprecated(note = "use cover with a Square")]
+    pub fn king_cover(&self, piece_id: &u32, row: &usize, col: &u32) -> &KingCover {
+        self.cover(piece_id, MoveMap::square(row, col))
+    }
+    pub fn king_moves(&self, square: Square) -> &MovePaths {
+        &self.king[square.row()][square.col() as usize]
+    }
+    pub fn knight_moves(&self, square: Square) -> &MovePaths {
+        &self.knight[square.row()][square.col() as usize]
+    }
+    pub fn queen_moves(&self, square: Square) -> &MovePaths {
+        &self.queen[square.row()][square.col() as usize]
+    }
+    pub fn bishop_moves(&self, square: Square) -> &MovePaths {
+        &self.bishop[square.row()][square.col() as usize]
+    }
+    pub fn rook_moves(&self, square: Square) -> &MovePaths {
+        &self.rook[square.row()][square.col() as usize]
+    }
+    pub fn pawn_moves(&self, square: Square, piece_id: &u32) -> &PawnMoveMap {
         if *piece_id < 6 {
-            &self.p1_pawn[*row][*col as usize]
+            &self.p1_pawn[square.row()][square.col() as usize]
         } else {
-            &self.p2_pawn[*row][*col as usize]
+            &self.p2_pawn[square.row()][square.col() as usize]
         }
     }
-    /// returns all the ways the piece at the row and col could be attacked
-    pub fn king_cover(&self, piece_id: &u32, row: &usize, col: &u32) -> &KingCover {
+    /// returns all the ways the piece on the square could be attacked
+    pub fn cover(&self, piece_id: &u32, square: Square) -> &KingCover {
         if *piece_id < 6 {
-            &self.p1_check_king[*row][*col as usize]
+            &self.p1_check_king[square.row()][square.col() as usize]
         } else {
-            &self.p2_check_king[*row][*col as usize]
+            &self.p2_check_king[square.row()][square.col() as usize]
         }
     }
+    /// the square for the row and col of the old signatures, which panicked off the board
+    fn square(row: &usize, col: &u32) -> Square {
+        Square::from_row_col(*row, *col).expect("row and col must be less than 8")
+    }
     pub fn new() -> MoveMap {
         MoveMap {
             row_col: MoveMap::row_col(),
@@ -0,0 +1,112 @@
+// this module counts the leaves of the move tree, the numbers are compared with published
+// tables to find move generation bugs
+
+use std::ops::AddAssign;
+
+use crate::chess::{ChessInstant, Move};
+
+/// the leaves found at the depth with how many of the moves into them were of each kind,
+/// the same columns as the classic perft tables
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PerftCounts {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub checkmates: u64,
+}
+
+impl AddAssign for PerftCounts {
+    fn add_assign(&mut self, other: PerftCounts) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+/// counts the positions reached after depth plie from the chess instant,
+/// a depth of 0 counts the chess instant itself
+pub fn perft(chess_instant: &ChessInstant, depth: u32) -> PerftCounts {
+    let mut counts = PerftCounts::default();
+    if depth == 0 {
+        counts.nodes = 1;
+        return counts;
+    }
+    let mut ci = *chess_instant;
+    walk(&mut ci, depth, &mut counts);
+    counts
+}
+
+/// the perft counts under each legal move from the chess instant, in move generation order,
+/// a depth of 0 has no moves to divide by so gives back nothing
+pub fn perft_divide(chess_instant: &ChessInstant, depth: u32) -> Vec<(Move, PerftCounts)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut ci = *chess_instant;
+    legal(&ci)
+        .into_iter()
+        .map(|mv| {
+            let mut counts = PerftCounts::default();
+            if depth == 1 {
+                leaf(&mut ci, mv, &mut counts);
+            } else {
+                let undo = ci.make_move(mv);
+                walk(&mut ci, depth - 1, &mut counts);
+                ci.unmake_move(undo);
+            }
+            (mv, counts)
+        })
+        .collect()
+}
+
+/// walks the tree with make and unmake, adding the moves made at the last plie to counts
+fn walk(ci: &mut ChessInstant, depth: u32, counts: &mut PerftCounts) {
+    for mv in legal(ci) {
+        if depth == 1 {
+            leaf(ci, mv, counts);
+        } else {
+            let undo = ci.make_move(mv);
+            walk(ci, depth - 1, counts);
+            ci.unmake_move(undo);
+        }
+    }
+}
+
+/// counts the move and the position it leads to
+fn leaf(ci: &mut ChessInstant, mv: Move, counts: &mut PerftCounts) {
+    counts.nodes += 1;
+    if mv.captured.is_some() {
+        counts.captures += 1;
+    }
+    if mv.en_passant {
+        counts.en_passants += 1;
+    }
+    if mv.castle {
+        counts.castles += 1;
+    }
+    if mv.promotion.is_some() {
+        counts.promotions += 1;
+    }
+    let undo = ci.make_move(mv);
+    if ci.checker_bits(&ci.player()) != 0 {
+        counts.checks += 1;
+        if ci.evasion_moves().is_empty() {
+            counts.checkmates += 1;
+        }
+    }
+    ci.unmake_move(undo);
+}
+
+/// the legal moves without needing a move map
+fn legal(ci: &ChessInstant) -> Vec<Move> {
+    let mut moves = ci.pseudo_legal_moves();
+    ci.retain_legal(&mut moves);
+    moves
+}
@@ -0,0 +1,476 @@
+// this module reads games written as Portable Game Notation, replaying the mainline of each game
+// so the bots can be fed every position that came up, and writes games back out as pgn
+
+use std::fmt;
+
+use crate::chess::{ChessInstant, Player};
+use crate::fen::{FenError, STARTING_FEN};
+use crate::game::Game;
+use crate::movemap::MoveMap;
+use crate::san::SanError;
+use crate::status::GameStatus;
+
+/// the reason a pgn string could not be read
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PgnError {
+    /// a tag pair that is not written as [Name "value"]
+    InvalidTag(String),
+    /// a '{' comment that is never closed
+    UnterminatedComment,
+    /// a ')' without a '(' before it or a '(' that is never closed
+    UnbalancedVariation,
+    /// the FEN tag could not be read
+    Fen(FenError),
+    /// the move could not be played, game and ply count from 1 and ply counts the mainline moves of the game
+    IllegalMove {
+        game: usize,
+        ply: usize,
+        token: String,
+        reason: SanError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::InvalidTag(s) => write!(f, "'{s}' is not a valid pgn tag pair"),
+            PgnError::UnterminatedComment => write!(f, "pgn comment is never closed"),
+            PgnError::UnbalancedVariation => write!(f, "pgn variation parentheses do not match"),
+            PgnError::Fen(e) => write!(f, "pgn FEN tag: {e}"),
+            PgnError::IllegalMove {
+                game,
+                ply,
+                token,
+                reason,
+            } => write!(f, "game {game} ply {ply} '{token}': {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PgnError::Fen(e) => Some(e),
+            PgnError::IllegalMove { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+/// a game read from pgn, the mainline replayed from the start position with the tags and annotations
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PgnGame {
+    tags: Vec<(String, String)>,
+    game: Game,
+    result: String,
+    comments: Vec<(usize, String)>,
+    nags: Vec<(usize, u8)>,
+}
+
+impl PgnGame {
+    /// every tag pair in the order they were written
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// the value of the first tag with the name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// the mainline replayed, game.instants() is every position and game.moves() every move
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// the positions of the mainline from the start to the last move
+    pub fn instants(&self) -> &[ChessInstant] {
+        self.game.instants()
+    }
+
+    /// the result written after the moves, "1-0", "0-1", "1/2-1/2" or "*" if the game was not finished
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+
+    /// the mainline comments, each with the number of moves played before it
+    pub fn comments(&self) -> &[(usize, String)] {
+        &self.comments
+    }
+
+    /// the numeric annotation glyphs like $1 for a good move, each with the number of moves played before it
+    pub fn nags(&self) -> &[(usize, u8)] {
+        &self.nags
+    }
+}
+
+// the pieces of a pgn string
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    Symbol(String),
+}
+
+/// reads every game in the pgn, replaying the mainlines and skipping variations
+pub fn read_pgn(pgn: &str, mm: &MoveMap) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut tags = Vec::new();
+    let mut tokens = Vec::new();
+    let mut in_moves = false;
+    for token in tokenize(pgn)? {
+        match token {
+            Token::Tag(name, value) => {
+                // tags after moves without a result start the next game
+                if in_moves {
+                    let game = replay(std::mem::take(&mut tags), &tokens, None, games.len(), mm)?;
+                    games.push(game);
+                    tokens.clear();
+                    in_moves = false;
+                }
+                tags.push((name, value));
+            }
+            Token::Result(result) => {
+                games.push(replay(
+                    std::mem::take(&mut tags),
+                    &tokens,
+                    Some(result),
+                    games.len(),
+                    mm,
+                )?);
+                tokens.clear();
+                in_moves = false;
+            }
+            _ => {
+                tokens.push(token);
+                in_moves = true;
+            }
+        }
+    }
+    if in_moves || !tags.is_empty() {
+        games.push(replay(tags, &tokens, None, games.len(), mm)?);
+    }
+    Ok(games)
+}
+
+/// plays the moves of one game, index is the number of games read before it
+fn replay(
+    tags: Vec<(String, String)>,
+    tokens: &[Token],
+    result: Option<String>,
+    index: usize,
+    mm: &MoveMap,
+) -> Result<PgnGame, PgnError> {
+    let start = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => ChessInstant::from_fen(fen).map_err(PgnError::Fen)?,
+        None => ChessInstant::new(),
+    };
+    let mut pgn_game = PgnGame {
+        game: Game::from_instant(start),
+        result: result.unwrap_or_else(|| "*".to_string()),
+        tags,
+        comments: Vec::new(),
+        nags: Vec::new(),
+    };
+
+    let mut depth = 0;
+    for token in tokens {
+        let played = pgn_game.game.moves().len();
+        match token {
+            Token::Open => depth += 1,
+            Token::Close if depth == 0 => return Err(PgnError::UnbalancedVariation),
+            Token::Close => depth -= 1,
+            // only the mainline is replayed
+            _ if depth > 0 => {}
+            Token::Comment(comment) => pgn_game.comments.push((played, comment.clone())),
+            Token::Nag(nag) => pgn_game.nags.push((played, *nag)),
+            Token::Symbol(san) => {
+                let current = pgn_game.game.current();
+                let mv = current
+                    .parse_san(san, mm)
+                    .map_err(|reason| PgnError::IllegalMove {
+                        game: index + 1,
+                        ply: played + 1,
+                        token: san.clone(),
+                        reason,
+                    })?;
+                pgn_game.game.play(mv);
+            }
+            Token::Tag(..) | Token::Result(_) => {}
+        }
+    }
+    if depth > 0 {
+        return Err(PgnError::UnbalancedVariation);
+    }
+    Ok(pgn_game)
+}
+
+/// splits the pgn into tokens, dropping move numbers, escaped lines and "e.p." markers
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.char_indices().peekable();
+    let mut line_start = true;
+    while let Some((i, c)) = chars.next() {
+        let at_line_start = line_start;
+        line_start = c == '\n';
+        match c {
+            c if c.is_whitespace() => {}
+            // a line starting with '%' is skipped
+            '%' if at_line_start => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        line_start = true;
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                let mut comment = String::new();
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        line_start = true;
+                        break;
+                    }
+                    comment.push(c);
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, c)) => comment.push(c),
+                        None => return Err(PgnError::UnterminatedComment),
+                    }
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '[' => {
+                let mut tag = String::new();
+                let mut quoted = false;
+                loop {
+                    match chars.next() {
+                        Some((_, ']')) if !quoted => break,
+                        Some((_, '"')) => {
+                            quoted = !quoted;
+                            tag.push('"');
+                        }
+                        Some((_, '\\')) if quoted => {
+                            tag.push('\\');
+                            if let Some((_, c)) = chars.next() {
+                                tag.push(c);
+                            }
+                        }
+                        Some((_, c)) => tag.push(c),
+                        None => {
+                            let line = pgn[i..].lines().next().unwrap_or_default();
+                            return Err(PgnError::InvalidTag(line.to_string()));
+                        }
+                    }
+                }
+                tokens.push(parse_tag(&tag)?);
+            }
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            _ => {
+                let mut symbol = c.to_string();
+                while let Some((_, c)) = chars.peek() {
+                    if c.is_whitespace() || "{}[]();$%".contains(*c) {
+                        break;
+                    }
+                    symbol.push(*c);
+                    chars.next();
+                }
+                // a nag that is not a number is kept as a symbol so it is reported as a bad move
+                if let Some(Ok(nag)) = symbol.strip_prefix('$').map(str::parse) {
+                    tokens.push(Token::Nag(nag));
+                    continue;
+                }
+                match symbol.as_str() {
+                    "1-0" | "0-1" | "1/2-1/2" | "*" => tokens.push(Token::Result(symbol)),
+                    "e.p." => {}
+                    _ => {
+                        // move numbers like "12." or "12..." may be written against the move
+                        let number = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+                        let san = match number.strip_prefix('.') {
+                            Some(san) => san.trim_start_matches('.'),
+                            None => &symbol,
+                        };
+                        if !san.is_empty() {
+                            tokens.push(Token::Symbol(san.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// reads the inside of a tag pair like Event "Club Night" with '\' escaping '"' and '\' in the value
+fn parse_tag(tag: &str) -> Result<Token, PgnError> {
+    let invalid = || PgnError::InvalidTag(format!("[{tag}]"));
+    let (name, value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid)?;
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(invalid());
+    }
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.push(chars.next().ok_or_else(invalid)?);
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Ok(Token::Tag(name.to_string(), unescaped))
+}
+
+// the tags every pgn game starts with and the value used when one is not given
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// the tags naming a bot and the depth it searched for the player, for use with write_pgn
+pub fn bot_tags(player: &Player, name: &str, search_depth: i32) -> Vec<(String, String)> {
+    let color = match player {
+        Player::P1 => "White",
+        Player::P2 => "Black",
+    };
+    vec![
+        (color.to_string(), name.to_string()),
+        (format!("{color}SearchDepth"), search_depth.to_string()),
+    ]
+}
+
+// scores this close to the ends of i32 are the searches counting plies to a mate
+const MATE_PLIES: i32 = 1_000;
+
+/// the score of a move from the view of the player who made it as text from white's view,
+/// in pawns or as #N when a player mates in N more moves with a negative N when black mates
+fn eval_text(score: i32, mover: Player) -> String {
+    // plies from the move to the mate and whether the player who moved is the one mating
+    let mate = if score >= i32::MAX - MATE_PLIES {
+        Some((i32::MAX - score, true))
+    } else if score <= i32::MIN + MATE_PLIES {
+        Some((score - i32::MIN, false))
+    } else {
+        None
+    };
+    let white = mover == Player::P1;
+    match mate {
+        Some((plies, mover_mates)) => {
+            let sign = if mover_mates == white { "" } else { "-" };
+            format!("#{sign}{}", (plies + 1) / 2)
+        }
+        None => {
+            let score = if white { score } else { -score };
+            format!("{:.2}", score as f64 / 100.0)
+        }
+    }
+}
+
+/// writes the game as pgn, the seven tag roster comes first with any of its tags found in tags
+/// and the rest of tags after in order, evals holds a minimax score for each move from the view
+/// of the player who made it and is written as an [%eval] comment from white's view in pawns
+/// or moves to mate,
+/// the result is worked out from the game unless a Result tag is given
+pub fn write_pgn(
+    game: &Game,
+    tags: &[(String, String)],
+    evals: &[Option<i32>],
+    mm: &MoveMap,
+) -> String {
+    let given = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let status = game.status(mm);
+    let result = given("Result").unwrap_or(match status {
+        _ if !status.is_over() => "*",
+        GameStatus::Checkmate { winner: Player::P1 } => "1-0",
+        GameStatus::Checkmate { winner: Player::P2 } => "0-1",
+        _ => "1/2-1/2",
+    });
+
+    let mut pgn = String::new();
+    for (name, default) in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => result,
+            _ => given(name).unwrap_or(default),
+        };
+        pgn.push_str(&tag_line(name, value));
+    }
+    let start = game.instants()[0];
+    if start.to_fen() != STARTING_FEN && given("FEN").is_none() {
+        pgn.push_str(&tag_line("SetUp", "1"));
+        pgn.push_str(&tag_line("FEN", &start.to_fen()));
+    }
+    for (name, value) in tags {
+        if !SEVEN_TAG_ROSTER.iter().any(|(roster, _)| roster == name) {
+            pgn.push_str(&tag_line(name, value));
+        }
+    }
+    pgn.push('\n');
+
+    let mut words = Vec::new();
+    for (i, (ci, mv)) in game.instants().iter().zip(game.moves()).enumerate() {
+        let number = ci.plie.div_ceil(2);
+        if ci.player() == Player::P1 {
+            words.push(format!("{number}."));
+        } else if i == 0 {
+            words.push(format!("{number}..."));
+        }
+        words.push(ci.to_san(*mv, mm));
+        if let Some(Some(score)) = evals.get(i) {
+            words.push(format!("{{[%eval {}]}}", eval_text(*score, ci.player())));
+        }
+    }
+    words.push(result.to_string());
+
+    // export format keeps lines to at most 80 characters
+    let mut line = String::new();
+    for word in words {
+        if !line.is_empty() && line.len() + 1 + word.len() > 80 {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    pgn.push_str(&line);
+    pgn.push('\n');
+    pgn
+}
+
+/// a tag pair line with '"' and '\' escaped in the value
+fn tag_line(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{name} \"{value}\"]\n")
+}
@@ -0,0 +1,216 @@
+// this module reads and writes moves as Standard Algebraic Notation like "Nbd7", "exd6" or "e8=Q+"
+
+use std::fmt;
+
+use crate::chess::{ChessInstant, Move, Piece};
+use crate::movemap::MoveMap;
+use crate::square::Square;
+
+/// the reason a san string could not be resolved to a move
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SanError {
+    /// the string is not written as a san move
+    Invalid(String),
+    /// no legal move from the position matches the string
+    Illegal(String),
+    /// more than one legal move matches the string, candidates holds the san for each of them
+    Ambiguous {
+        san: String,
+        candidates: Vec<String>,
+    },
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Invalid(s) => write!(f, "'{s}' is not a valid san move"),
+            SanError::Illegal(s) => write!(f, "'{s}' is not a legal move in the position"),
+            SanError::Ambiguous { san, candidates } => {
+                write!(f, "'{san}' could be any of {}", candidates.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl ChessInstant {
+    /// writes the legal move as san, with the piece disambiguated only as far as needed
+    /// and a '+' or '#' when the move gives check or mate
+    pub fn to_san(&self, mv: Move, mm: &MoveMap) -> String {
+        let mut san = if mv.castle {
+            if mv.to.col() == 6 { "O-O" } else { "O-O-O" }.to_string()
+        } else {
+            let mut san = String::new();
+            if mv.piece == Piece::Pawn {
+                if mv.captured.is_some() {
+                    san.push(mv.from.file().to_char());
+                }
+            } else {
+                san.push(piece_letter(&mv.piece));
+                san.push_str(&self.disambiguation(mv, mm));
+            }
+            if mv.captured.is_some() {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_string());
+            if let Some(promotion) = mv.promotion {
+                san.push('=');
+                san.push(piece_letter(&promotion));
+            }
+            san
+        };
+
+        let next = self.apply(mv);
+        if next.in_check(mm, &next.king_id()) {
+            let mate = next.legal_moves(mm).is_empty();
+            san.push(if mate { '#' } else { '+' });
+        }
+        san
+    }
+
+    /// finds the one legal move the san describes, check and mate suffixes, annotations
+    /// like "!?" and a trailing "e.p." are accepted but not required
+    pub fn parse_san(&self, san: &str, mm: &MoveMap) -> Result<Move, SanError> {
+        let invalid = || SanError::Invalid(san.to_string());
+        let text = san.trim();
+        let text = text.strip_suffix("e.p.").unwrap_or(text).trim_end();
+        let text = text.trim_end_matches(['+', '#', '!', '?']);
+        if text.is_empty() {
+            return Err(invalid());
+        }
+
+        let legal = self.legal_moves(mm);
+        let castle = match text {
+            "O-O" | "0-0" => Some(6),
+            "O-O-O" | "0-0-0" => Some(2),
+            _ => None,
+        };
+        if let Some(col) = castle {
+            return legal
+                .into_iter()
+                .find(|mv| mv.castle && mv.to.col() == col)
+                .ok_or(SanError::Illegal(san.to_string()));
+        }
+
+        let (piece, rest) = match text.chars().next().and_then(letter_piece) {
+            Some(piece) => (piece, &text[1..]),
+            None => (Piece::Pawn, text),
+        };
+        let (rest, promotion) = match rest.split_once('=') {
+            Some((rest, letter)) => {
+                let mut chars = letter.chars();
+                let promotion = chars.next().and_then(letter_piece).ok_or_else(invalid)?;
+                if chars.next().is_some() {
+                    return Err(invalid());
+                }
+                (rest, Some(promotion))
+            }
+            // a promotion may also be written without the '=' like "e8Q"
+            None => match rest.chars().last().and_then(letter_piece) {
+                Some(promotion) if piece == Piece::Pawn => {
+                    (&rest[..rest.len() - 1], Some(promotion))
+                }
+                _ => (rest, None),
+            },
+        };
+        if promotion == Some(Piece::King) || promotion == Some(Piece::Pawn) {
+            return Err(invalid());
+        }
+        if rest.len() < 2 || !rest.is_ascii() {
+            return Err(invalid());
+        }
+        let (prefix, square) = rest.split_at(rest.len() - 2);
+        let to: Square = square.parse().map_err(|_| invalid())?;
+        let (prefix, capture) = match prefix.strip_suffix('x') {
+            Some(prefix) => (prefix, true),
+            None => (prefix, false),
+        };
+
+        let mut from_col = None;
+        let mut from_row = None;
+        for c in prefix.chars() {
+            match c {
+                'a'..='h' if from_col.is_none() && from_row.is_none() => {
+                    from_col = Some(c as u32 - 'a' as u32)
+                }
+                '1'..='8' if from_row.is_none() => from_row = Some(8 - (c as usize - '0' as usize)),
+                _ => return Err(invalid()),
+            }
+        }
+        // a pawn without a file moves straight forward
+        if piece == Piece::Pawn && from_col.is_none() {
+            from_col = Some(to.col());
+        }
+
+        let found: Vec<Move> = legal
+            .into_iter()
+            .filter(|mv| {
+                !mv.castle
+                    && mv.piece == piece
+                    && mv.to == to
+                    && mv.promotion == promotion
+                    && from_col.is_none_or(|col| mv.from.col() == col)
+                    && from_row.is_none_or(|row| mv.from.row() == row)
+                    && (!capture || mv.captured.is_some())
+            })
+            .collect();
+        match found[..] {
+            [] => Err(SanError::Illegal(san.to_string())),
+            [mv] => Ok(mv),
+            _ => Err(SanError::Ambiguous {
+                san: san.to_string(),
+                candidates: found.iter().map(|mv| self.to_san(*mv, mm)).collect(),
+            }),
+        }
+    }
+
+    /// the position after the move the san describes
+    pub fn play_san(&self, san: &str, mm: &MoveMap) -> Result<ChessInstant, SanError> {
+        Ok(self.apply(self.parse_san(san, mm)?))
+    }
+
+    /// the file, rank or square needed to tell the move apart from the same piece moving to the same square
+    fn disambiguation(&self, mv: Move, mm: &MoveMap) -> String {
+        let from = mv.from;
+        let others: Vec<Square> = self
+            .legal_moves(mm)
+            .into_iter()
+            .filter(|other| other.piece == mv.piece && other.to == mv.to && other.from != from)
+            .map(|other| other.from)
+            .collect();
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|other| other.file() != from.file()) {
+            from.file().to_string()
+        } else if others.iter().all(|other| other.rank() != from.rank()) {
+            from.rank().to_string()
+        } else {
+            from.to_string()
+        }
+    }
+}
+
+/// the upper case letter for a piece, pawns are written without a letter but 'P' is given back
+fn piece_letter(piece: &Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Rook => 'R',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+/// the piece for an upper case letter, lower case letters are files so give back none
+fn letter_piece(c: char) -> Option<Piece> {
+    match c {
+        'R' => Some(Piece::Rook),
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
@@ -0,0 +1,97 @@
+// this module works out static exchange evaluation, the material won or lost when both players
+// keep taking on a square with their cheapest piece until it no longer pays
+
+use crate::bitboard;
+use crate::chess::{ChessInstant, Move, Piece, Player};
+
+/// the centipawn values the exchanges are counted in
+fn see_value(piece: &Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 100,
+        Piece::Knight => 300,
+        Piece::Bishop => 300,
+        Piece::Rook => 500,
+        Piece::Queen => 900,
+        Piece::King => 20_000,
+    }
+}
+
+// cheapest first, the order attackers join the exchange
+const ATTACKER_ORDER: [Piece; 6] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+    Piece::King,
+];
+
+impl ChessInstant {
+    /// the material in centipawns the current player ends up with after the move and every
+    /// capture back on its square, each side taking with its least valuable piece and stopping
+    /// when taking on would lose material, pieces lined up behind a taker join in as x-rays.
+    /// pins are not looked at and a move that takes nothing gives 0 or less
+    pub fn see(&self, mv: Move) -> i32 {
+        if mv.castle {
+            return 0;
+        }
+        let from = mv.from.index();
+        let to = mv.to.index();
+        let mut occupied = self.bits.occupied() & !(1 << from);
+        if mv.en_passant {
+            // the taken pawn is beside the moving pawn not on the square it moves to
+            occupied &= !(1 << (from - from % 8 + to % 8));
+        }
+
+        // gains[d] is what the side making capture d wins if the exchange stops after it
+        let mut gains = [0; 32];
+        gains[0] = mv.captured.map_or(0, |piece| see_value(&piece));
+        let mut on_square = see_value(&mv.piece);
+        if let Some(promotion) = mv.promotion {
+            gains[0] += see_value(&promotion) - see_value(&Piece::Pawn);
+            on_square = see_value(&promotion);
+        }
+
+        let straight = self.piece_bits(Piece::Rook) | self.piece_bits(Piece::Queen);
+        let diagonal = self.piece_bits(Piece::Bishop) | self.piece_bits(Piece::Queen);
+        let mut attackers = self.attackers_to(to, occupied) & occupied;
+        let mut side = self.player().swap();
+        let mut depth = 0;
+        while depth < gains.len() - 1 {
+            let own = attackers & self.bits.player(&side);
+            let Some((piece, bits)) = ATTACKER_ORDER
+                .iter()
+                .map(|piece| (*piece, own & self.bits.pieces(piece.to_id(&side))))
+                .find(|(_, bits)| *bits != 0)
+            else {
+                break;
+            };
+            // the king can only take when nothing can take it back
+            if piece == Piece::King && attackers & self.bits.player(&side.swap()) != 0 {
+                break;
+            }
+            depth += 1;
+            gains[depth] = on_square - gains[depth - 1];
+            on_square = see_value(&piece);
+
+            occupied &= !(1 << bits.trailing_zeros());
+            // sliders behind the piece that just took can now reach the square
+            attackers |= bitboard::rook_attacks(to, occupied) & straight
+                | bitboard::bishop_attacks(to, occupied) & diagonal;
+            attackers &= occupied;
+            side = side.swap();
+        }
+
+        // each side can choose not to take, so settle the gains back from the last capture
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+        gains[0]
+    }
+
+    /// the squares holding the piece for either player
+    fn piece_bits(&self, piece: Piece) -> u64 {
+        self.bits.pieces(piece.to_id(&Player::P1)) | self.bits.pieces(piece.to_id(&Player::P2))
+    }
+}
@@ -0,0 +1,232 @@
+// this module holds the typed board coordinates, a square is stored as row * 8 + col
+// the same as the bitboards, so row 0 col 0 is a8 and row 7 col 7 is h1
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ChessError;
+
+/// a column of the board, 0 is the a file and 7 is the h file
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(try_from = "u8")]
+pub struct File(u8);
+
+/// a rank of the board counted from white's side, 0 is the first rank and 7 is the eighth
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(try_from = "u8")]
+pub struct Rank(u8);
+
+/// one of the 64 squares of the board
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(try_from = "u8")]
+pub struct Square(u8);
+
+/// the reason a string could not be read as a square like "e4"
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseSquareError(pub String);
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a square", self.0)
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+impl File {
+    /// the file for a column between 0 and 7
+    pub fn new(col: u32) -> Option<File> {
+        (col < 8).then_some(File(col as u8))
+    }
+
+    /// the file for a lower case letter between 'a' and 'h'
+    pub fn from_char(c: char) -> Option<File> {
+        ('a'..='h').contains(&c).then(|| File(c as u8 - b'a'))
+    }
+
+    /// the lower case letter of the file
+    pub fn to_char(&self) -> char {
+        (b'a' + self.0) as char
+    }
+
+    /// the column of the board the file is, the col used by board_get
+    pub fn col(&self) -> u32 {
+        self.0 as u32
+    }
+
+    /// every file from a to h
+    pub fn all() -> impl Iterator<Item = File> {
+        (0..8).map(File)
+    }
+}
+
+impl Rank {
+    /// the rank for an index between 0 for the first rank and 7 for the eighth
+    pub fn new(index: u8) -> Option<Rank> {
+        (index < 8).then_some(Rank(index))
+    }
+
+    /// the rank for a digit between '1' and '8'
+    pub fn from_char(c: char) -> Option<Rank> {
+        ('1'..='8').contains(&c).then(|| Rank(c as u8 - b'1'))
+    }
+
+    /// the digit of the rank
+    pub fn to_char(&self) -> char {
+        (b'1' + self.0) as char
+    }
+
+    /// 0 for the first rank up to 7 for the eighth
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    /// the row of the board the rank is, the row used by board_get with row 0 being the eighth rank
+    pub fn row(&self) -> usize {
+        7 - self.0 as usize
+    }
+
+    /// every rank from the first to the eighth
+    pub fn all() -> impl Iterator<Item = Rank> {
+        (0..8).map(Rank)
+    }
+}
+
+impl Square {
+    /// the square on the file and rank
+    pub fn new(file: File, rank: Rank) -> Square {
+        Square(rank.row() as u8 * 8 + file.0)
+    }
+
+    /// the square at the row and column, none if either is greater than 7
+    pub fn from_row_col(row: usize, col: u32) -> Option<Square> {
+        (row < 8 && col < 8).then(|| Square(row as u8 * 8 + col as u8))
+    }
+
+    /// the square for an index of row * 8 + col, none if the index is 64 or more
+    pub fn from_index(index: u8) -> Option<Square> {
+        (index < 64).then_some(Square(index))
+    }
+
+    /// the square for a row and column already known to be on the board
+    pub(crate) fn at(row: usize, col: u32) -> Square {
+        debug_assert!(row < 8 && col < 8);
+        Square(row as u8 * 8 + col as u8)
+    }
+
+    /// the square for a bitboard index already known to be on the board
+    pub(crate) fn at_index(index: usize) -> Square {
+        debug_assert!(index < 64);
+        Square(index as u8)
+    }
+
+    /// row * 8 + col, the index used by the bitboards
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    /// the row of the square, row 0 is the eighth rank
+    pub fn row(&self) -> usize {
+        (self.0 >> 3) as usize
+    }
+
+    /// the column of the square, col 0 is the a file
+    pub fn col(&self) -> u32 {
+        (self.0 & 7) as u32
+    }
+
+    pub fn file(&self) -> File {
+        File(self.0 & 7)
+    }
+
+    pub fn rank(&self) -> Rank {
+        Rank(7 - (self.0 >> 3))
+    }
+
+    /// every square by index, going across each row from a8 to h1
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    /// reads a square written like "e4"
+    fn from_str(s: &str) -> Result<Square, ParseSquareError> {
+        let error = || ParseSquareError(s.to_string());
+        let mut chars = s.chars();
+        let file = chars.next().and_then(File::from_char).ok_or_else(error)?;
+        let rank = chars.next().and_then(Rank::from_char).ok_or_else(error)?;
+        if chars.next().is_some() {
+            return Err(error());
+        }
+        Ok(Square::new(file, rank))
+    }
+}
+
+impl From<Square> for (usize, u32) {
+    fn from(square: Square) -> (usize, u32) {
+        (square.row(), square.col())
+    }
+}
+
+impl TryFrom<(usize, u32)> for Square {
+    type Error = ChessError;
+
+    fn try_from((row, col): (usize, u32)) -> Result<Square, ChessError> {
+        Square::from_row_col(row, col).ok_or(ChessError::OutOfBounds { row, col })
+    }
+}
+
+impl TryFrom<u8> for File {
+    type Error = ChessError;
+
+    fn try_from(index: u8) -> Result<File, ChessError> {
+        (index < 8)
+            .then_some(File(index))
+            .ok_or(ChessError::IndexOutOfBounds(index))
+    }
+}
+
+impl TryFrom<u8> for Rank {
+    type Error = ChessError;
+
+    fn try_from(index: u8) -> Result<Rank, ChessError> {
+        Rank::new(index).ok_or(ChessError::IndexOutOfBounds(index))
+    }
+}
+
+impl TryFrom<u8> for Square {
+    type Error = ChessError;
+
+    fn try_from(index: u8) -> Result<Square, ChessError> {
+        Square::from_index(index).ok_or(ChessError::IndexOutOfBounds(index))
+    }
+}
+
+impl From<Square> for u8 {
+    fn from(square: Square) -> u8 {
+        square.0
+    }
+}
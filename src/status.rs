@@ -0,0 +1,108 @@
+// this module decides if a game is over and why, so the bots and the frontend share one definition
+
+use crate::chess::{ChessInstant, Player};
+use crate::game::Game;
+use crate::movemap::MoveMap;
+use serde::{Deserialize, Serialize};
+
+/// the state of a game, either still being played or over with the reason it ended
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GameStatus {
+    Ongoing,
+    /// the player to move is in check and has no legal moves
+    Checkmate {
+        winner: Player,
+    },
+    /// the player to move is not in check and has no legal moves
+    Stalemate,
+    /// neither player has the pieces to checkmate
+    InsufficientMaterial,
+    /// fifty moves by each player without a capture or pawn move, play goes on unless a player claims the draw
+    FiftyMoveRule,
+    /// the same position came up three times, play goes on unless a player claims the draw
+    Repetition,
+    /// seventy five moves by each player without a capture or pawn move
+    SeventyFiveMoveRule,
+    /// the same position came up five times
+    FivefoldRepetition,
+}
+
+impl GameStatus {
+    /// the game has ended, a draw that has to be claimed does not end it
+    pub fn is_over(&self) -> bool {
+        !matches!(
+            self,
+            GameStatus::Ongoing | GameStatus::FiftyMoveRule | GameStatus::Repetition
+        )
+    }
+
+    /// the game is still being played but the player to move can claim a draw
+    pub fn can_claim_draw(&self) -> bool {
+        matches!(self, GameStatus::FiftyMoveRule | GameStatus::Repetition)
+    }
+
+    /// the game has ended without a winner
+    pub fn is_draw(&self) -> bool {
+        self.is_over() && self.winner().is_none()
+    }
+
+    /// the player that won the game, none if the game is not over or is a draw
+    pub fn winner(&self) -> Option<Player> {
+        match self {
+            GameStatus::Checkmate { winner } => Some(*winner),
+            _ => None,
+        }
+    }
+}
+
+impl ChessInstant {
+    /// the status of the position on its own, repeated positions need the history kept by Game::status
+    pub fn status(&self, mm: &MoveMap) -> GameStatus {
+        let legality = self.legality();
+        let has_moves = self
+            .pseudo_legal_moves()
+            .into_iter()
+            .any(|mv| self.is_legal_with(mv, &legality));
+        self.status_with_moves(mm, has_moves)
+    }
+
+    /// the same as status when it is already known if the player to move has a legal move
+    pub(crate) fn status_with_moves(&self, mm: &MoveMap, has_moves: bool) -> GameStatus {
+        if !has_moves {
+            if self.in_check(mm, &self.king_id()) {
+                return GameStatus::Checkmate {
+                    winner: self.player().swap(),
+                };
+            }
+            return GameStatus::Stalemate;
+        }
+        // checkmate on the last move of the fifty still wins so it is checked after
+        if self.is_insufficient_material() {
+            return GameStatus::InsufficientMaterial;
+        }
+        if self.is_seventy_five_move_draw() {
+            return GameStatus::SeventyFiveMoveRule;
+        }
+        if self.is_fifty_move_draw() {
+            return GameStatus::FiftyMoveRule;
+        }
+        GameStatus::Ongoing
+    }
+}
+
+impl Game {
+    /// the status of the current position including draws by repetition
+    pub fn status(&self, mm: &MoveMap) -> GameStatus {
+        let status = self.current().status(mm);
+        if status.is_over() {
+            return status;
+        }
+        if self.is_fivefold_repetition() {
+            return GameStatus::FivefoldRepetition;
+        }
+        if status == GameStatus::Ongoing && self.is_threefold_repetition() {
+            return GameStatus::Repetition;
+        }
+        status
+    }
+}
@@ -0,0 +1,101 @@
+// this module reads and writes moves in the long algebraic form used by uci engines like "e2e4" or "e7e8q"
+
+use std::fmt;
+
+use crate::chess::{ChessInstant, Move, Piece};
+use crate::movemap::MoveMap;
+use crate::square::Square;
+
+/// the reason a uci string could not be resolved to a move
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UciError {
+    /// the string is not two squares and an optional promotion letter
+    Invalid(String),
+    /// no legal move from the position matches the string
+    Illegal(String),
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciError::Invalid(s) => write!(f, "'{s}' is not a valid uci move"),
+            UciError::Illegal(s) => write!(f, "'{s}' is not a legal move in the position"),
+        }
+    }
+}
+
+impl std::error::Error for UciError {}
+
+impl Move {
+    /// writes the move as the from and to squares with a lower case promotion letter,
+    /// castling is written as the king moving two squares like "e1g1"
+    pub fn to_uci(&self) -> String {
+        let mut uci = self.from.to_string();
+        uci.push_str(&self.to.to_string());
+        if let Some(promotion) = self.promotion {
+            uci.push(match promotion {
+                Piece::Rook => 'r',
+                Piece::Knight => 'n',
+                Piece::Bishop => 'b',
+                _ => 'q',
+            });
+        }
+        uci
+    }
+
+    /// finds the legal move from the position the uci string describes, castling may be
+    /// given as the king moving two squares or as the king taking its own rook like "e1h1",
+    /// in chess960 only the king taking its rook is read as castling
+    pub fn from_uci(uci: &str, ci: &ChessInstant, mm: &MoveMap) -> Result<Move, UciError> {
+        let invalid = || UciError::Invalid(uci.to_string());
+        let text = uci.trim();
+        if !text.is_ascii() || text.len() < 4 || text.len() > 5 {
+            return Err(invalid());
+        }
+        let from: Square = text[0..2].parse().map_err(|_| invalid())?;
+        let to: Square = text[2..4].parse().map_err(|_| invalid())?;
+        let promotion = match text[4..].chars().next() {
+            None => None,
+            Some('r') => Some(Piece::Rook),
+            Some('n') => Some(Piece::Knight),
+            Some('b') => Some(Piece::Bishop),
+            Some('q') => Some(Piece::Queen),
+            Some(_) => return Err(invalid()),
+        };
+
+        ci.legal_moves(mm)
+            .into_iter()
+            .find(|mv| {
+                mv.from == from
+                    && mv.promotion == promotion
+                    && if mv.castle {
+                        to == ci.castle_rook_square(mv) || !ci.is_chess960() && mv.to == to
+                    } else {
+                        mv.to == to
+                    }
+            })
+            .ok_or(UciError::Illegal(uci.to_string()))
+    }
+}
+
+impl ChessInstant {
+    /// the position after the move the uci string describes
+    pub fn play_uci(&self, uci: &str, mm: &MoveMap) -> Result<ChessInstant, UciError> {
+        Ok(self.apply(Move::from_uci(uci, self, mm)?))
+    }
+
+    /// writes the move from the position as uci, the same as Move::to_uci apart from
+    /// castling in chess960 which is written as the king taking its own rook like "g1h1"
+    pub fn move_to_uci(&self, mv: Move) -> String {
+        if !(self.is_chess960() && mv.castle) {
+            return mv.to_uci();
+        }
+        mv.from.to_string() + &self.castle_rook_square(&mv).to_string()
+    }
+
+    /// the square the rook starts on for the castling move
+    fn castle_rook_square(&self, mv: &Move) -> Square {
+        let (rook_col, _) = self.castle_rook_cols(&self.player(), &mv.to.col());
+        Square::at(mv.from.row(), rook_col)
+    }
+}
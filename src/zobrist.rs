@@ -0,0 +1,91 @@
+// this module gives every chess instant a zobrist key so the same position can be found again,
+// the key is kept up to date by board_set for the pieces and by make_move for the rest of the state
+
+use crate::bitboard::{self, Squares};
+use crate::chess::{ChessInstant, Piece, Player};
+use crate::square::Square;
+
+// one key per piece id per square, id 6 (no piece) is left as 0 so empty squares do not change the key
+const PIECE_KEYS: usize = 13 * 64;
+const SIDE_KEY: usize = PIECE_KEYS;
+const CASTLE_KEYS: usize = SIDE_KEY + 1;
+const PASSANT_KEYS: usize = CASTLE_KEYS + 4;
+const KEY_COUNT: usize = PASSANT_KEYS + 8;
+
+const KEYS: [u64; KEY_COUNT] = keys();
+
+/// the key for the piece id standing on the square index
+pub(crate) fn piece_key(id: u32, square: usize) -> u64 {
+    if id == 6 || id > 12 {
+        return 0;
+    }
+    KEYS[id as usize * 64 + square]
+}
+
+impl ChessInstant {
+    /// the zobrist key of the position, the same pieces, player to move, castles and
+    /// en passant give the same key no matter what moves were played to get there
+    pub fn hash_key(&self) -> u64 {
+        self.hash
+    }
+
+    /// the key for everything but the pieces, make_move takes it out before the move and adds it back after
+    pub(crate) fn state_key(&self) -> u64 {
+        let mut key = 0;
+        if self.player() == Player::P2 {
+            key ^= KEYS[SIDE_KEY];
+        }
+        let (wq, wk, bq, bk) = self.valid_castles;
+        for (i, valid) in [wq, wk, bq, bk].iter().enumerate() {
+            if *valid {
+                key ^= KEYS[CASTLE_KEYS + i];
+            }
+        }
+        // the passant only changes the position if a pawn could take it
+        let (passant, player) = match self.player() {
+            Player::P1 => (self.p2_passant, Player::P1),
+            Player::P2 => (self.p1_passant, Player::P2),
+        };
+        if passant < 64 {
+            let square = passant as usize;
+            let pawns = self.bits.pieces(Piece::Pawn.to_id(&player));
+            let takers = bitboard::PAWN_ATTACKS[bitboard::side(&player.swap())][square];
+            if pawns & takers != 0 {
+                key ^= KEYS[PASSANT_KEYS + square % 8];
+            }
+        }
+        key
+    }
+
+    /// works out the full key from scratch
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let mut key = self.state_key();
+        for square in Squares(self.bits.occupied()) {
+            let (row, col) = bitboard::row_col(square);
+            key ^= piece_key(self.id_at(Square::at(row, col)), square);
+        }
+        key
+    }
+}
+
+/// fills the keys with the splitmix64 sequence so they are the same every build
+const fn keys() -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x5eed_c4e5_5eed_c4e5;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    // empty squares do not change the key
+    let mut square = 0;
+    while square < 64 {
+        keys[6 * 64 + square] = 0;
+        square += 1;
+    }
+    keys
+}